@@ -8,8 +8,6 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::{env, fs};
 
-const BINDINGS_DEST: &'static str = "src/bindings.rs";
-
 /// Headers which can't be used from Rust.
 const HEADER_BLACKLIST: &'static [&'static str] = &[
     "avr/crc16.h", "avr/parity.h", "avr/delay.h", // Deprecated, moved to 'util'
@@ -27,8 +25,68 @@ const DEVICE_SPECIFIC_HEADERS: &'static [&'static str] = &[
     "util/crc16.h",
 ];
 
+/// Microcontrollers which can be selected through a cargo feature of the same name.
+const MCU_FEATURES: &'static [&'static str] = &[
+    "atmega48p", "atmega168", "atmega328p", "atmega1280", "atmega2560",
+    "attiny85", "attiny88",
+];
+
+/// Installation prefixes searched for avr-libc by the `system-avr-libc` feature.
+const SYSTEM_LIBC_PREFIXES: &'static [&'static str] = &[
+    "/usr/lib/avr", "/usr/avr", "/usr/local/avr", "/opt/local/avr",
+];
+
+/// Alternative `vfprintf`/`vfscanf` implementations shipped with avr-libc,
+/// as (cargo feature, library, symbol replaced).
+const STDIO_FLAVOURS: &'static [(&'static str, &'static str, &'static str)] = &[
+    ("printf-min", "printf_min", "vfprintf"),
+    ("printf-float", "printf_flt", "vfprintf"),
+    ("scanf-min", "scanf_min", "vfscanf"),
+    ("scanf-float", "scanf_flt", "vfscanf"),
+];
+
+/// The oldest installed avr-libc version (major, minor) that can be linked against.
+const MIN_SYSTEM_LIBC_VERSION: (u32, u32) = (2, 0);
+
 lazy_static! {
-    static ref MCU: Option<Mcu> = avr_mcu::current::mcu();
+    static ref MCU: Option<Mcu> = selected_mcu();
+}
+
+/// Gets the microcontroller to build for.
+///
+/// An mcu cargo feature takes precedence over the cpu named in the target
+/// specification, which allows host and documentation builds to pick a device.
+fn selected_mcu() -> Option<Mcu> {
+    let enabled: Vec<&str> = MCU_FEATURES.iter()
+        .cloned()
+        .filter(|name| is_feature_enabled(name))
+        .collect();
+
+    match enabled.len() {
+        0 if avr_mcu::current::is_compiling_for_avr() => avr_mcu::current::mcu(),
+        0 => None,
+        1 => {
+            let name = enabled[0];
+
+            if avr_mcu::current::is_compiling_for_avr() {
+                match avr_mcu::current::mcu_name() {
+                    Some(ref target_cpu) if target_cpu != name => {
+                        println!("cargo:warning=the '{}' feature overrides the target cpu '{}'", name, target_cpu);
+                    },
+                    _ => (),
+                }
+            }
+
+            Some(avr_mcu::microcontroller(name))
+        },
+        _ => panic!("only one microcontroller feature may be enabled at a time, found: {}",
+                    enabled.join(", ")),
+    }
+}
+
+fn is_feature_enabled(name: &str) -> bool {
+    let var = format!("CARGO_FEATURE_{}", name.to_uppercase().replace('-', "_"));
+    env::var_os(var).is_some()
 }
 
 fn architecture() -> avr_mcu::Architecture {
@@ -40,28 +98,252 @@ fn architecture() -> avr_mcu::Architecture {
 
 fn main() {
     if MCU.is_none() {
-        println!("cargo:warning=not targeting a specific microcontroller, create a custom target specification to enable mcu-specific functionality");
+        println!("cargo:warning=not targeting a specific microcontroller, enable an mcu feature or create a custom target specification to enable mcu-specific functionality");
     }
 
     let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
-    let libc_dir = manifest_dir.join("avr-libc");
-    let include_dir = libc_dir.join("include");
-    let arch_dir = libc_dir.join("avr").join("lib").join(architecture().name());
+    let system_libc_dir = system_libc_dir();
+    let libc_dir = match system_libc_dir {
+        Some(ref dir) => dir.clone(),
+        None => manifest_dir.join("avr-libc"),
+    };
+
+    let bindings_path = bindings_path();
+    generate_bindings(&libc_dir, &bindings_path);
+    export_bindings(&bindings_path);
+    println!("cargo:rustc-env=AVR_LIBC_BINDINGS={}", bindings_path.display());
+
+    emit_device_cfgs();
+
+    println!("cargo:rerun-if-changed=avr-libc");
+    println!("cargo:rerun-if-env-changed=AVR_LIBC_EXPORT_BINDINGS");
+
+    let arch_dir = match system_libc_dir {
+        Some(ref dir) => {
+            check_system_libc_version(dir);
+            system_arch_dir(dir)
+        },
+        None => build_libc(&libc_dir),
+    };
+
+    println!("cargo:rustc-link-search={}", arch_dir.display());
+    link_stdio_flavours();
+    println!("cargo:rustc-link-lib=static=c");
+}
+
+/// Links the `vfprintf` and `vfscanf` implementations selected through cargo features.
+///
+/// The libraries must come before libc. The `printf` and `scanf` modules
+/// reference the replaced symbol so that the linker takes it from them
+/// instead of from libc.
+fn link_stdio_flavours() {
+    for symbol in &["vfprintf", "vfscanf"] {
+        let enabled: Vec<_> = STDIO_FLAVOURS.iter()
+            .filter(|&&(feature, _, replaced)| replaced == *symbol && is_feature_enabled(feature))
+            .collect();
+
+        match enabled.len() {
+            0 => (),
+            1 => {
+                let (_, library, _) = *enabled[0];
+
+                println!("cargo:rustc-link-lib=static={}", library);
+            },
+            _ => panic!("only one {} flavour may be enabled at a time, found: {}", symbol,
+                        enabled.iter().map(|&&(feature, _, _)| feature).collect::<Vec<_>>().join(", ")),
+        }
+    }
+}
+
+/// Tells rustc which device-specific parts of the API are available.
+fn emit_device_cfgs() {
+    println!("cargo:rustc-check-cfg=cfg(avr_libc_eeprom)");
+    println!("cargo:rustc-check-cfg=cfg(avr_libc_far_progmem)");
+
+    if let Some(ref mcu) = *MCU {
+        if address_space_size(mcu, "eeprom") > 0 {
+            println!("cargo:rustc-cfg=avr_libc_eeprom");
+        }
+
+        // Flash beyond 64 KiB can only be reached through the `*_PF` routines.
+        if address_space_size(mcu, "prog") > 0x10000 {
+            println!("cargo:rustc-cfg=avr_libc_far_progmem");
+        }
+    }
+}
+
+/// Gets the size in bytes of one of the address spaces of a microcontroller.
+fn address_space_size(mcu: &Mcu, id: &str) -> u32 {
+    mcu.device.address_spaces.iter()
+        .find(|space| space.id == id)
+        .map(|space| space.size)
+        .unwrap_or(0)
+}
+
+/// Builds the vendored avr-libc if necessary.
+///
+/// Returns the directory containing the static libraries for the current architecture.
+fn build_libc(libc_dir: &Path) -> PathBuf {
+    let build_dir = build_dir();
+    let source_dir = build_dir.join("src");
+    let object_dir = build_dir.join("obj");
+    let include_dir = object_dir.join("include");
+    let arch_dir = object_dir.join("avr").join("lib").join(architecture().name());
     let static_lib_path = arch_dir.join("libc.a");
 
     if !static_lib_path.exists() {
         println!("avr-libc not yet built for '{}', building now", architecture().name());
-        bootstrap(&libc_dir);
-        configure(&libc_dir);
+
+        // Start from scratch so that a previously failed build cannot leave stale files behind.
+        if build_dir.exists() {
+            fs::remove_dir_all(&build_dir).expect("failed to clean avr-libc build directory");
+        }
+
+        // Bootstrapping generates files next to the sources, so it is done on a private copy.
+        copy_dir(libc_dir, &source_dir);
+        bootstrap(&source_dir);
+        configure(&source_dir, &object_dir);
 
         make(&include_dir);
         make(&arch_dir);
     }
 
-    generate_bindings(&libc_dir);
+    arch_dir
+}
 
-    println!("cargo:rustc-link-search={}", arch_dir.display());
-    println!("cargo:rustc-link-lib=static=c");
+/// Gets the installation prefix of a system-wide avr-libc, if one should be used.
+///
+/// `AVR_LIBC_PATH` always takes precedence. Otherwise, the `system-avr-libc`
+/// feature searches the usual installation prefixes.
+///
+/// The build is redone when the installation is upgraded.
+fn system_libc_dir() -> Option<PathBuf> {
+    println!("cargo:rerun-if-env-changed=AVR_LIBC_PATH");
+
+    let prefix = if let Some(path) = env::var_os("AVR_LIBC_PATH") {
+        let path = PathBuf::from(path);
+
+        if !path.join("include").join("avr").join("version.h").exists() {
+            panic!("AVR_LIBC_PATH is set to '{}', which does not contain an avr-libc installation",
+                   path.display());
+        }
+
+        path
+    } else if cfg!(feature = "system-avr-libc") {
+        let found = SYSTEM_LIBC_PREFIXES.iter()
+            .map(PathBuf::from)
+            .find(|prefix| prefix.join("include").join("avr").join("version.h").exists());
+
+        match found {
+            Some(prefix) => prefix,
+            None => panic!("could not find a system avr-libc in any of {:?}, set AVR_LIBC_PATH to its installation prefix",
+                           SYSTEM_LIBC_PREFIXES),
+        }
+    } else {
+        return None;
+    };
+
+    println!("cargo:rerun-if-changed={}", prefix.join("include").join("avr").join("version.h").display());
+    Some(prefix)
+}
+
+/// Gets the directory of an installed avr-libc holding the libraries for the current architecture.
+fn system_arch_dir(prefix: &Path) -> PathBuf {
+    let lib_dir = prefix.join("lib");
+
+    // The libraries for the baseline architecture are installed directly into `lib`.
+    let arch_dir = match architecture() {
+        avr_mcu::Architecture::Avr2 => lib_dir,
+        arch => lib_dir.join(arch.name()),
+    };
+
+    if !arch_dir.join("libc.a").exists() {
+        panic!("the system avr-libc at '{}' has no libc.a for '{}'",
+               prefix.display(), architecture().name());
+    }
+
+    arch_dir
+}
+
+/// Ensures that an installed avr-libc is recent enough to match the bindings.
+fn check_system_libc_version(prefix: &Path) {
+    let version_header = prefix.join("include").join("avr").join("version.h");
+    let contents = fs::read_to_string(&version_header)
+        .expect(&format!("failed to read '{}'", version_header.display()));
+
+    let define = |name: &str| -> u32 {
+        contents.lines()
+            .filter_map(|line| {
+                let mut words = line.split_whitespace();
+                match (words.next(), words.next(), words.next()) {
+                    (Some("#define"), Some(n), Some(value)) if n == name => value.parse().ok(),
+                    _ => None,
+                }
+            })
+            .next()
+            .expect(&format!("'{}' does not define {}", version_header.display(), name))
+    };
+
+    let version = (define("__AVR_LIBC_MAJOR__"), define("__AVR_LIBC_MINOR__"));
+
+    if version < MIN_SYSTEM_LIBC_VERSION {
+        panic!("the system avr-libc at '{}' is version {}.{}, but at least {}.{} is required",
+               prefix.display(), version.0, version.1,
+               MIN_SYSTEM_LIBC_VERSION.0, MIN_SYSTEM_LIBC_VERSION.1);
+    }
+}
+
+/// Gets the directory avr-libc is built in.
+///
+/// It is keyed by architecture and microcontroller so that builds for
+/// different targets never share object files.
+fn build_dir() -> PathBuf {
+    out_dir().join("avr-libc").join(architecture().name()).join(mcu_name())
+}
+
+/// Gets the file the bindings for the current microcontroller are written to.
+fn bindings_path() -> PathBuf {
+    out_dir().join("bindings").join(format!("{}.rs", mcu_name()))
+}
+
+/// Copies freshly generated bindings into `AVR_LIBC_EXPORT_BINDINGS`, if set.
+fn export_bindings(bindings_path: &Path) {
+    if let Some(export_dir) = env::var_os("AVR_LIBC_EXPORT_BINDINGS") {
+        let dest = Path::new(&export_dir).join(format!("{}.rs", mcu_name()));
+
+        fs::create_dir_all(&export_dir).unwrap();
+        fs::copy(bindings_path, &dest)
+            .expect(&format!("failed to export bindings to '{}'", dest.display()));
+    }
+}
+
+fn out_dir() -> PathBuf {
+    PathBuf::from(env::var("OUT_DIR").unwrap())
+}
+
+/// Gets the name used to key per-device build artifacts.
+fn mcu_name() -> String {
+    match *MCU {
+        Some(ref mcu) => mcu.device.name.to_lowercase(),
+        None => "generic".to_owned(),
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) {
+    fs::create_dir_all(to).unwrap();
+
+    for entry in fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        let path = entry.path();
+        let dest = to.join(entry.file_name());
+
+        if path.is_dir() {
+            copy_dir(&path, &dest);
+        } else {
+            fs::copy(&path, &dest)
+                .expect(&format!("failed to copy '{}'", path.display()));
+        }
+    }
 }
 
 fn bootstrap(libc_dir: &Path) {
@@ -78,13 +360,15 @@ fn bootstrap(libc_dir: &Path) {
     }
 }
 
-fn configure(libc_dir: &Path) {
+fn configure(libc_dir: &Path, object_dir: &Path) {
     println!("Configuring avr-libc");
 
     let host = env::var("HOST").unwrap();
 
+    fs::create_dir_all(object_dir).unwrap();
+
     let mut cmd = Command::new("sh");
-    cmd.arg("configure");
+    cmd.arg(libc_dir.join("configure"));
     cmd.arg(&format!("--build={}", host));
     cmd.arg("--host=avr");
 
@@ -93,7 +377,7 @@ fn configure(libc_dir: &Path) {
         cmd.env("CFLAGS", format!("-mmcu={}", mcu.device.name.to_lowercase()));
     }
 
-    cmd.current_dir(libc_dir);
+    cmd.current_dir(object_dir);
     println!("{:?}", cmd);
 
     if !cmd.status().expect("failed to configure avr-libc").success() {
@@ -171,12 +455,12 @@ fn mcu_define_name() -> Option<&'static str> {
     MCU.as_ref().map(|mcu| &mcu.c_preprocessor_name[..])
 }
 
-fn generate_bindings(libc_dir: &Path) {
+fn generate_bindings(libc_dir: &Path, dest: &Path) {
     // Configure and generate bindings.
     let mut builder = bindgen::builder()
         .use_core()
         .ctypes_prefix("::rust_ctypes")
-        .clang_arg("-Iavr-libc/include")
+        .clang_arg(format!("-I{}", libc_dir.join("include").display()))
         .clang_arg("-ffreestanding");
 
     if let Some(define_name) = mcu_define_name() {
@@ -192,7 +476,8 @@ fn generate_bindings(libc_dir: &Path) {
         .expect("failed to create bindings");
 
     // Write the generated bindings to an output file.
-    bindings.write_to_file(BINDINGS_DEST)
+    fs::create_dir_all(dest.parent().unwrap()).unwrap();
+    bindings.write_to_file(dest)
         .expect("could not write bindings to file");
 }
 
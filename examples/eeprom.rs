@@ -9,9 +9,14 @@
 
 #![feature(lang_items, unwind_attributes)]
 
+#[macro_use]
 extern crate avr_libc;
 
-use avr_libc::{eeprom_read_dword, eeprom_write_dword, random};
+use avr_libc::random;
+
+eemem! {
+    static FAILED_ATTEMPTS: u32 = 0;
+}
 
 #[no_mangle]
 pub extern fn main() {
@@ -28,17 +33,11 @@ pub extern fn main() {
 }
 
 fn load_failed_attempts() -> u32 {
-    unsafe {
-        let addr = 12 as *mut u32; // chosen arbitrarily
-        eeprom_read_dword(addr)
-    }
+    FAILED_ATTEMPTS.read()
 }
 
 fn save_failed_attempts(attempts: u32) {
-    unsafe {
-        let addr = 12 as *mut u32; // chosen arbitrarily
-        eeprom_write_dword(addr, attempts);
-    }
+    FAILED_ATTEMPTS.write(attempts);
 }
 
 // These do not need to be in a module, but we group them here for clarity.
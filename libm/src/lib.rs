@@ -0,0 +1,11 @@
+//! The math functions of the [`libm`](https://docs.rs/libm) crate, implemented by avr-libc.
+//!
+//! This crate takes the place of `libm` through `[patch.crates-io]`, so that
+//! dependencies which use it call into avr-libc instead. All of its functions
+//! are re-exported from [`avr_libc::libm`](../avr_libc/libm/index.html).
+
+#![no_std]
+
+extern crate avr_libc;
+
+pub use avr_libc::libm::*;
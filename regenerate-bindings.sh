@@ -0,0 +1,36 @@
+#! /bin/sh -ea
+
+# Generates the bindings of every supported microcontroller into 'bindings/'.
+#
+# This needs everything a regular build needs: avr-gcc, the autotools and libclang.
+#
+# With '--check', the bindings are generated into a temporary directory instead,
+# and the script fails if any of the ones in 'bindings/' are missing or out of date.
+
+SCRIPT_DIR=$(dirname $0)
+cd "${SCRIPT_DIR}"
+
+MCUS="atmega48p atmega168 atmega328p atmega1280 atmega2560 attiny85 attiny88"
+BINDINGS_DIR="$(pwd)/bindings"
+
+generate() {
+  for mcu in ${MCUS}; do
+    echo "Generating bindings for ${mcu}"
+    AVR_LIBC_EXPORT_BINDINGS="$1" cargo build --features "${mcu}"
+  done
+}
+
+if [ "$1" = "--check" ]; then
+  CHECK_DIR=$(mktemp -d)
+  trap 'rm -rf "${CHECK_DIR}"' EXIT
+
+  generate "${CHECK_DIR}"
+
+  if ! diff -r "${BINDINGS_DIR}" "${CHECK_DIR}"; then
+    echo "the bindings in 'bindings/' are missing or out of date, run ./regenerate-bindings.sh" >&2
+    exit 1
+  fi
+else
+  mkdir -p "${BINDINGS_DIR}"
+  generate "${BINDINGS_DIR}"
+fi
@@ -0,0 +1,207 @@
+//! A global allocator backed by avr-libc's `malloc`.
+//!
+//! ```ignore
+//! extern crate alloc;
+//!
+//! #[global_allocator]
+//! static ALLOCATOR: AvrLibcAllocator = AvrLibcAllocator;
+//! ```
+//!
+//! By default the heap starts after `.bss` and grows up to `__malloc_margin`
+//! bytes below the stack pointer. Both can be changed before the first
+//! allocation, see [`set_heap`](fn.set_heap.html) and
+//! [`set_heap_margin`](fn.set_heap_margin.html).
+
+use bindings::{size_t, malloc, calloc, realloc, free};
+use bindings::{__malloc_heap_start, __malloc_heap_end, __malloc_margin};
+use memory::__brkval;
+use rust_ctypes::{c_char, c_void};
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::mem::{self, MaybeUninit};
+use core::{fmt, ptr};
+
+/// The alignment of every pointer returned by `malloc`.
+const MALLOC_ALIGN: usize = 1;
+
+/// A hook called when an allocation fails.
+static mut OOM_HOOK: Option<fn(Layout)> = None;
+
+/// A [`GlobalAlloc`](https://doc.rust-lang.org/core/alloc/trait.GlobalAlloc.html)
+/// implementation using `malloc`, `calloc`, `realloc` and `free`.
+///
+/// `malloc` makes no alignment guarantees, so allocations with an alignment
+/// above 1 are over-allocated and the original pointer is stored in front of
+/// the aligned block.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct AvrLibcAllocator;
+
+unsafe impl GlobalAlloc for AvrLibcAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = if layout.align() <= MALLOC_ALIGN {
+            malloc(layout.size() as size_t) as *mut u8
+        } else {
+            alloc_aligned(layout)
+        };
+        check_oom(ptr, layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        if layout.align() <= MALLOC_ALIGN {
+            check_oom(calloc(1, layout.size() as size_t) as *mut u8, layout)
+        } else {
+            let ptr = self.alloc(layout);
+            if !ptr.is_null() {
+                ptr::write_bytes(ptr, 0, layout.size());
+            }
+            ptr
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if layout.align() <= MALLOC_ALIGN {
+            free(ptr as *mut c_void)
+        } else {
+            free(original_ptr(ptr) as *mut c_void)
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+
+        if layout.align() <= MALLOC_ALIGN {
+            return check_oom(realloc(ptr as *mut c_void, new_size as size_t) as *mut u8, new_layout);
+        }
+
+        let new_ptr = self.alloc(new_layout);
+        if !new_ptr.is_null() {
+            ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+            self.dealloc(ptr, layout);
+        }
+        new_ptr
+    }
+}
+
+/// Allocates a block with an alignment `malloc` does not guarantee.
+unsafe fn alloc_aligned(layout: Layout) -> *mut u8 {
+    let header = mem::size_of::<*mut u8>();
+    let size = match layout.size().checked_add(header + layout.align() - 1) {
+        Some(size) => size,
+        None => return ptr::null_mut(),
+    };
+
+    let raw = malloc(size as size_t) as *mut u8;
+    if raw.is_null() {
+        return raw;
+    }
+
+    let start = raw as usize + header;
+    let aligned = raw.add(header + (layout.align() - start % layout.align()) % layout.align());
+    ptr::write_unaligned((aligned as *mut *mut u8).offset(-1), raw);
+    aligned
+}
+
+/// Gets the pointer returned by `malloc` for a block from `alloc_aligned`.
+unsafe fn original_ptr(aligned: *mut u8) -> *mut u8 {
+    ptr::read_unaligned((aligned as *mut *mut u8).offset(-1))
+}
+
+/// Calls the out of memory hook if an allocation failed.
+unsafe fn check_oom(ptr: *mut u8, layout: Layout) -> *mut u8 {
+    if ptr.is_null() {
+        if let Some(hook) = *ptr::addr_of!(OOM_HOOK) {
+            hook(layout);
+        }
+    }
+    ptr
+}
+
+/// Sets a function to be called with the requested layout whenever an allocation fails.
+///
+/// The hook runs before the failure is reported to the caller, which for most
+/// collections means before `alloc::alloc::handle_alloc_error` is called. It
+/// is a good place to log the failure or reset the device.
+pub fn set_oom_hook(hook: fn(Layout)) {
+    unsafe { *ptr::addr_of_mut!(OOM_HOOK) = Some(hook) }
+}
+
+/// Removes the hook set with [`set_oom_hook`](fn.set_oom_hook.html).
+pub fn clear_oom_hook() {
+    unsafe { *ptr::addr_of_mut!(OOM_HOOK) = None }
+}
+
+/// An error returned when changing the heap layout after memory has been allocated.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HeapInUse;
+
+impl fmt::Display for HeapInUse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("the heap has already been used")
+    }
+}
+
+/// Checks if `malloc` has handed out any memory yet.
+pub fn heap_in_use() -> bool {
+    unsafe { !(*ptr::addr_of!(__brkval)).is_null() }
+}
+
+/// Places the heap in a static buffer instead of between `.bss` and the stack.
+///
+/// This must be done before the first allocation.
+pub fn set_heap(region: &'static mut [MaybeUninit<u8>]) -> Result<(), HeapInUse> {
+    if heap_in_use() {
+        return Err(HeapInUse);
+    }
+
+    let range = region.as_mut_ptr_range();
+    unsafe {
+        *ptr::addr_of_mut!(__malloc_heap_start) = range.start as *mut c_char;
+        *ptr::addr_of_mut!(__malloc_heap_end) = range.end as *mut c_char;
+    }
+    Ok(())
+}
+
+/// Sets the start and end of the heap.
+///
+/// A null `end` lets the heap grow up to `__malloc_margin` bytes below the
+/// stack pointer.
+///
+/// # Safety
+///
+/// The memory between `start` and `end` must be unused by anything else, for
+/// example external RAM. This must be done before the first allocation.
+pub unsafe fn set_heap_bounds(start: *mut u8, end: *mut u8) -> Result<(), HeapInUse> {
+    if heap_in_use() {
+        return Err(HeapInUse);
+    }
+
+    *ptr::addr_of_mut!(__malloc_heap_start) = start as *mut c_char;
+    *ptr::addr_of_mut!(__malloc_heap_end) = end as *mut c_char;
+    Ok(())
+}
+
+/// Gets the start and end of the heap, the end is null if the heap grows towards the stack.
+pub fn heap_bounds() -> (*mut u8, *mut u8) {
+    unsafe {
+        (*ptr::addr_of!(__malloc_heap_start) as *mut u8,
+         *ptr::addr_of!(__malloc_heap_end) as *mut u8)
+    }
+}
+
+/// Sets how many bytes below the stack pointer the heap must stay.
+///
+/// This only applies while the heap grows towards the stack, and must be done
+/// before the first allocation.
+pub fn set_heap_margin(margin: usize) -> Result<(), HeapInUse> {
+    if heap_in_use() {
+        return Err(HeapInUse);
+    }
+
+    unsafe { *ptr::addr_of_mut!(__malloc_margin) = margin as size_t }
+    Ok(())
+}
+
+/// Gets how many bytes below the stack pointer the heap must stay.
+pub fn heap_margin() -> usize {
+    unsafe { *ptr::addr_of!(__malloc_margin) as usize }
+}
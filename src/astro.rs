@@ -0,0 +1,189 @@
+//! The position of the sun and moon, from avr-libc's astronomical time functions.
+//!
+//! Functions which depend on where the observer is take a
+//! [`GeoPosition`](struct.GeoPosition.html). The results are approximations,
+//! good to a few minutes, which is plenty for switching lights or opening vents.
+//!
+//! ```ignore
+//! let greenhouse = GeoPosition::from_degrees(52.37, 4.89).unwrap();
+//! let sunset = greenhouse.sun_set(AvrInstant::now());
+//! ```
+
+use bindings::{time_t, ONE_DAY, ONE_DEGREE};
+use bindings::{set_position, daylight_seconds, solar_noon, sun_rise, sun_set, lm_sidereal};
+use bindings::{equation_of_time as raw_equation_of_time, solar_declination as raw_solar_declination};
+use bindings::{gm_sidereal, moon_phase as raw_moon_phase};
+use time::AvrInstant;
+
+use core::fmt;
+use core::time::Duration;
+
+/// An error returned when creating a [`GeoPosition`](struct.GeoPosition.html)
+/// from an out of range latitude or longitude.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct InvalidPosition;
+
+impl fmt::Display for InvalidPosition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("latitude or longitude out of range")
+    }
+}
+
+/// A position on earth, stored in seconds of arc like avr-libc expects.
+///
+/// North latitudes and east longitudes are positive.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct GeoPosition {
+    latitude: i32,
+    longitude: i32,
+}
+
+impl GeoPosition {
+    /// Creates a position from a latitude between -90 and 90 degrees and a
+    /// longitude between -180 and 180 degrees.
+    pub fn from_degrees(latitude: f32, longitude: f32) -> Result<Self, InvalidPosition> {
+        // Also rejects NaN.
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return Err(InvalidPosition);
+        }
+
+        Ok(GeoPosition {
+            latitude: (latitude * ONE_DEGREE as f32) as i32,
+            longitude: (longitude * ONE_DEGREE as f32) as i32,
+        })
+    }
+
+    /// Creates a position from a latitude and longitude in seconds of arc.
+    pub fn from_arc_seconds(latitude: i32, longitude: i32) -> Result<Self, InvalidPosition> {
+        let max_latitude = 90 * ONE_DEGREE as i32;
+        let max_longitude = 180 * ONE_DEGREE as i32;
+
+        if latitude.abs() > max_latitude || longitude.abs() > max_longitude {
+            return Err(InvalidPosition);
+        }
+        Ok(GeoPosition { latitude, longitude })
+    }
+
+    /// Gets the latitude in degrees.
+    pub fn latitude(&self) -> f32 {
+        self.latitude as f32 / ONE_DEGREE as f32
+    }
+
+    /// Gets the longitude in degrees.
+    pub fn longitude(&self) -> f32 {
+        self.longitude as f32 / ONE_DEGREE as f32
+    }
+
+    /// Gets the latitude in seconds of arc.
+    pub fn latitude_arc_seconds(&self) -> i32 {
+        self.latitude
+    }
+
+    /// Gets the longitude in seconds of arc.
+    pub fn longitude_arc_seconds(&self) -> i32 {
+        self.longitude
+    }
+
+    /// Gets the time of sunrise on the (UTC) day of `day`.
+    ///
+    /// Returns `None` if the sun does not rise or set that day, inside a polar circle.
+    pub fn sun_rise(&self, day: AvrInstant) -> Option<AvrInstant> {
+        self.sun_up(day)?;
+        Some(self.call(sun_rise, day))
+    }
+
+    /// Gets the time of sunset on the (UTC) day of `day`.
+    ///
+    /// Returns `None` if the sun does not rise or set that day, inside a polar circle.
+    pub fn sun_set(&self, day: AvrInstant) -> Option<AvrInstant> {
+        self.sun_up(day)?;
+        Some(self.call(sun_set, day))
+    }
+
+    /// Gets the time the sun is highest in the sky on the (UTC) day of `day`.
+    pub fn solar_noon(&self, day: AvrInstant) -> AvrInstant {
+        self.call(solar_noon, day)
+    }
+
+    /// Gets how long the sun is above the horizon on the (UTC) day of `day`.
+    ///
+    /// Inside a polar circle this is zero in winter, and can exceed a day in summer.
+    pub fn daylight(&self, day: AvrInstant) -> Duration {
+        let secs = unsafe {
+            set_position(self.latitude, self.longitude);
+            daylight_seconds(&day.as_y2k_secs())
+        };
+        Duration::from_secs(secs.max(0) as u64)
+    }
+
+    /// Gets the local mean sidereal time, in seconds into the sidereal day.
+    pub fn local_sidereal(&self, time: AvrInstant) -> u32 {
+        unsafe {
+            set_position(self.latitude, self.longitude);
+            lm_sidereal(&time.as_y2k_secs()) as u32
+        }
+    }
+
+    /// Checks that the sun both rises and sets on the day of `day`.
+    fn sun_up(&self, day: AvrInstant) -> Option<()> {
+        let daylight = self.daylight(day).as_secs();
+
+        if daylight > 0 && daylight < ONE_DAY as u64 {
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn call(&self, f: unsafe extern "C" fn(*const time_t) -> time_t, time: AvrInstant) -> AvrInstant {
+        unsafe {
+            set_position(self.latitude, self.longitude);
+            AvrInstant::from_y2k_secs(f(&time.as_y2k_secs()))
+        }
+    }
+}
+
+/// The phase of the moon, as returned by `moon_phase`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MoonPhase(i8);
+
+impl MoonPhase {
+    /// Gets the phase of the moon at a point in time.
+    ///
+    /// The approximation ignores the influence of the sun and the planets, and
+    /// is often off by several hours.
+    pub fn at(time: AvrInstant) -> Self {
+        MoonPhase(unsafe { raw_moon_phase(&time.as_y2k_secs()) })
+    }
+
+    /// Gets the raw value, from -100 to 100, where the magnitude is the
+    /// percentage illuminated and the sign is negative while waning.
+    pub fn get(&self) -> i8 {
+        self.0
+    }
+
+    /// Gets the percentage of the moon which is illuminated.
+    pub fn illumination(&self) -> u8 {
+        self.0.unsigned_abs()
+    }
+
+    /// Checks if the illuminated part is growing.
+    pub fn is_waxing(&self) -> bool {
+        self.0 > 0
+    }
+}
+
+/// Gets the difference between apparent and mean solar time, in seconds.
+pub fn equation_of_time(time: AvrInstant) -> i16 {
+    unsafe { raw_equation_of_time(&time.as_y2k_secs()) }
+}
+
+/// Gets the declination of the sun, in radians.
+pub fn solar_declination(time: AvrInstant) -> f32 {
+    unsafe { raw_solar_declination(&time.as_y2k_secs()) }
+}
+
+/// Gets the Greenwich mean sidereal time, in seconds into the sidereal day.
+pub fn greenwich_sidereal(time: AvrInstant) -> u32 {
+    unsafe { gm_sidereal(&time.as_y2k_secs()) as u32 }
+}
@@ -0,0 +1,159 @@
+//! Calendar calculations on [`Tm`](../time/struct.Tm.html).
+//!
+//! Includes ISO 8601 week dates and the timestamps used by the FAT file system,
+//! so that files on an SD card can be stamped with the system time:
+//!
+//! ```ignore
+//! let stamp = FatTimestamp::now().unwrap();
+//! file.set_modified(stamp.date(), stamp.time());
+//! ```
+
+use bindings::{week_date, is_leap_year as raw_is_leap_year};
+use bindings::{iso_week_date_r, week_of_month as raw_week_of_month, week_of_year as raw_week_of_year};
+use rust_ctypes::c_int;
+use time::{AvrInstant, InvalidTime, Tm};
+
+use core::fmt;
+
+/// Checks if a year has 366 days.
+pub fn is_leap_year(year: i16) -> bool {
+    unsafe { raw_is_leap_year(year) != 0 }
+}
+
+/// Gets the week of the year, where weeks start on `first_weekday` (0 for Sunday).
+///
+/// Week 1 starts on the first `first_weekday` of the year, and the days before
+/// it are in week 0, so the result ranges from 0 to 53.
+pub fn week_of_year(tm: &Tm, first_weekday: u8) -> u8 {
+    unsafe { raw_week_of_year(tm.as_raw(), first_weekday % 7) }
+}
+
+/// Gets the week of the month, where weeks start on `first_weekday` (0 for Sunday).
+///
+/// Week 1 starts on the first `first_weekday` of the month, and the days before
+/// it are in week 0, so the result ranges from 0 to 5.
+pub fn week_of_month(tm: &Tm, first_weekday: u8) -> u8 {
+    unsafe { raw_week_of_month(tm.as_raw(), first_weekday % 7) }
+}
+
+/// A date in the ISO 8601 week calendar.
+///
+/// Weeks start on Monday, and week 1 is the week containing the first Thursday
+/// of the year, so the first and last days of a year can belong to a week of
+/// the previous or next year.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IsoWeekDate {
+    year: i16,
+    week: u8,
+    weekday: u8,
+}
+
+impl IsoWeekDate {
+    /// Gets the week date of a calendar date.
+    pub fn from_tm(tm: &Tm) -> Self {
+        let mut raw = week_date { year: 0, week: 0, day: 0 };
+        unsafe { iso_week_date_r(tm.year() as c_int, tm.year_day() as c_int, &mut raw) };
+
+        IsoWeekDate { year: raw.year, week: raw.week as u8, weekday: raw.day as u8 }
+    }
+
+    /// Gets the week-numbering year.
+    pub fn year(&self) -> i16 {
+        self.year
+    }
+
+    /// Gets the week, from 1 to 53.
+    pub fn week(&self) -> u8 {
+        self.week
+    }
+
+    /// Gets the day of the week, from 1 for Monday to 7 for Sunday.
+    pub fn weekday(&self) -> u8 {
+        self.weekday
+    }
+}
+
+impl fmt::Display for IsoWeekDate {
+    /// Formats the date like `2024-W03-2`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-W{:02}-{}", self.year, self.week, self.weekday)
+    }
+}
+
+/// A timestamp as stored in FAT directory entries.
+///
+/// The date is kept in the upper 16 bits and the time of day in the lower 16
+/// bits, with a resolution of two seconds. Timestamps range from 1980 to 2107
+/// and are usually in local time.
+///
+/// The `fatfs_time` declared in `time.h` cannot be linked against, as the
+/// library actually defines it as `fat_time`, so the conversion is done here.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FatTimestamp(u32);
+
+impl FatTimestamp {
+    /// The last year a timestamp can represent.
+    pub const MAX_YEAR: i16 = 2107;
+
+    /// Encodes a calendar time, rounding the seconds down to an even number.
+    ///
+    /// Returns `None` for years after [`MAX_YEAR`](#associatedconstant.MAX_YEAR).
+    pub fn from_tm(tm: &Tm) -> Option<Self> {
+        if tm.year() > Self::MAX_YEAR {
+            return None;
+        }
+
+        Some(FatTimestamp(
+            ((tm.year() as u32 - 1980) << 25) |
+            ((tm.month() as u32) << 21) |
+            ((tm.day() as u32) << 16) |
+            ((tm.hour() as u32) << 11) |
+            ((tm.minute() as u32) << 5) |
+            (tm.second() as u32 / 2)))
+    }
+
+    /// Encodes the current local time.
+    pub fn now() -> Option<Self> {
+        Self::from_tm(&AvrInstant::now().to_local())
+    }
+
+    /// Wraps a raw timestamp.
+    pub const fn from_raw(raw: u32) -> Self {
+        FatTimestamp(raw)
+    }
+
+    /// Combines the date and time fields of a directory entry.
+    pub const fn from_parts(date: u16, time: u16) -> Self {
+        FatTimestamp(((date as u32) << 16) | time as u32)
+    }
+
+    /// Gets the raw timestamp.
+    pub const fn get(&self) -> u32 {
+        self.0
+    }
+
+    /// Gets the date field of a directory entry.
+    pub const fn date(&self) -> u16 {
+        (self.0 >> 16) as u16
+    }
+
+    /// Gets the time field of a directory entry.
+    pub const fn time(&self) -> u16 {
+        self.0 as u16
+    }
+
+    /// Decodes the timestamp.
+    ///
+    /// Fails if any field is out of range, or if the date lies before 2000,
+    /// which avr-libc cannot represent.
+    pub fn to_tm(&self) -> Result<Tm, InvalidTime> {
+        let year = 1980 + (self.0 >> 25) as i16;
+        let month = ((self.0 >> 21) & 0x0f) as u8;
+        let day = ((self.0 >> 16) & 0x1f) as u8;
+        let hour = ((self.0 >> 11) & 0x1f) as u8;
+        let minute = ((self.0 >> 5) & 0x3f) as u8;
+        let second = (self.0 & 0x1f) as u8 * 2;
+
+        Tm::new(year, month, day, hour, minute, second)
+    }
+}
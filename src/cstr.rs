@@ -0,0 +1,336 @@
+//! NUL-terminated strings on top of the `string.h` routines.
+//!
+//! [`CStrBuf`](struct.CStrBuf.html) is a string with a fixed capacity which is
+//! always NUL-terminated, so it can be handed to C functions at any time.
+//! Appending to it truncates instead of overflowing, and reports when it did:
+//!
+//! ```ignore
+//! let mut path = CStrBuf::<32>::new();
+//! path.push_str("/logs/")?;
+//! write!(path, "{}.csv", day)?;
+//!
+//! for field in line.split(CStr::from_bytes_with_nul(b",\0").unwrap()) {
+//!     // ...
+//! }
+//! ```
+//!
+//! [`CStrRef`](struct.CStrRef.html) adds searching to borrowed strings.
+
+use bindings::{size_t, strlen, strlcpy, strlcat, strsep, strtok_r, strstr, strcasestr, memmem};
+use bindings::{strrev, strupr, strlwr};
+use rust_ctypes::{c_char, c_void};
+
+use core::ffi::CStr;
+use core::marker::PhantomData;
+use core::{fmt, ptr, slice};
+
+/// An error returned when a string did not fit into a [`CStrBuf`](struct.CStrBuf.html).
+///
+/// The buffer then holds as much of the string as fits.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Truncated;
+
+impl fmt::Display for Truncated {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("string truncated to fit the buffer")
+    }
+}
+
+/// A NUL-terminated string stored inline in an `N` byte buffer.
+///
+/// One byte is taken up by the terminator, so the string can be up to `N - 1`
+/// bytes long. `N` must not be zero.
+#[derive(Clone)]
+pub struct CStrBuf<const N: usize> {
+    buf: [u8; N],
+}
+
+impl<const N: usize> CStrBuf<N> {
+    const NOT_EMPTY: () = assert!(N > 0, "a CStrBuf needs room for the terminator");
+
+    /// Creates an empty string.
+    pub const fn new() -> Self {
+        let () = Self::NOT_EMPTY;
+        CStrBuf { buf: [0; N] }
+    }
+
+    /// Creates a string holding a copy of `s`, truncated to fit.
+    pub fn from_cstr(s: &CStr) -> Self {
+        let mut buf = Self::new();
+        let _ = buf.set(s);
+        buf
+    }
+
+    /// Gets the largest length the string can have.
+    pub const fn capacity(&self) -> usize {
+        N - 1
+    }
+
+    /// Gets the length of the string in bytes, not including the terminator.
+    pub fn len(&self) -> usize {
+        unsafe { strlen(self.as_ptr()) as usize }
+    }
+
+    /// Checks if the string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.buf[0] == 0
+    }
+
+    /// Empties the string.
+    pub fn clear(&mut self) {
+        self.buf[0] = 0;
+    }
+
+    /// Replaces the string with a copy of `s`, using `strlcpy`.
+    pub fn set(&mut self, s: &CStr) -> Result<(), Truncated> {
+        let len = unsafe { strlcpy(self.as_mut_ptr(), s.as_ptr(), N as size_t) };
+        check_truncation(len, N)
+    }
+
+    /// Appends `s` to the string, using `strlcat`.
+    pub fn push(&mut self, s: &CStr) -> Result<(), Truncated> {
+        let len = unsafe { strlcat(self.as_mut_ptr(), s.as_ptr(), N as size_t) };
+        check_truncation(len, N)
+    }
+
+    /// Appends a Rust string.
+    ///
+    /// The string ends at the first NUL byte in `s`, if there is one.
+    pub fn push_str(&mut self, s: &str) -> Result<(), Truncated> {
+        let bytes = s.as_bytes();
+        let bytes = match bytes.iter().position(|&byte| byte == 0) {
+            Some(nul) => &bytes[..nul],
+            None => bytes,
+        };
+
+        let len = self.len();
+        let copied = bytes.len().min(N - 1 - len);
+        self.buf[len..len + copied].copy_from_slice(&bytes[..copied]);
+        self.buf[len + copied] = 0;
+
+        if copied < bytes.len() { Err(Truncated) } else { Ok(()) }
+    }
+
+    /// Reverses the bytes of the string, using `strrev`.
+    pub fn reverse(&mut self) {
+        unsafe { strrev(self.as_mut_ptr()) };
+    }
+
+    /// Converts ASCII letters to upper case, using `strupr`.
+    pub fn make_ascii_uppercase(&mut self) {
+        unsafe { strupr(self.as_mut_ptr()) };
+    }
+
+    /// Converts ASCII letters to lower case, using `strlwr`.
+    pub fn make_ascii_lowercase(&mut self) {
+        unsafe { strlwr(self.as_mut_ptr()) };
+    }
+
+    /// Splits the string at every occurrence of any byte in `delimiters`, using `strsep`.
+    ///
+    /// Adjacent delimiters produce empty tokens. The delimiters are overwritten
+    /// with NUL bytes, so the string only holds the first token afterwards.
+    pub fn split<'a>(&'a mut self, delimiters: &'a CStr) -> Split<'a> {
+        Split { rest: self.as_mut_ptr(), delimiters, _buf: PhantomData }
+    }
+
+    /// Splits the string into tokens separated by any bytes in `delimiters`, using `strtok_r`.
+    ///
+    /// Unlike [`split`](#method.split), empty tokens are skipped. The string is
+    /// modified in the same way.
+    pub fn tokens<'a>(&'a mut self, delimiters: &'a CStr) -> Tokens<'a> {
+        Tokens { start: self.as_mut_ptr(), save: ptr::null_mut(), delimiters, _buf: PhantomData }
+    }
+
+    /// Borrows the string as a `CStr`.
+    pub fn as_cstr(&self) -> &CStr {
+        unsafe { CStr::from_ptr(self.as_ptr()) }
+    }
+
+    /// Borrows the string for searching.
+    pub fn as_cstr_ref(&self) -> CStrRef<'_> {
+        CStrRef::new(self.as_cstr())
+    }
+
+    /// Gets the bytes of the string, not including the terminator.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len()]
+    }
+
+    /// Gets a pointer to the string.
+    pub fn as_ptr(&self) -> *const c_char {
+        self.buf.as_ptr() as *const c_char
+    }
+
+    /// Gets a mutable pointer to the string, which must stay NUL-terminated within `N` bytes.
+    pub fn as_mut_ptr(&mut self) -> *mut c_char {
+        self.buf.as_mut_ptr() as *mut c_char
+    }
+}
+
+impl<const N: usize> Default for CStrBuf<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> PartialEq for CStrBuf<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl<const N: usize> Eq for CStrBuf<N> { }
+
+impl<const N: usize> PartialEq<CStr> for CStrBuf<N> {
+    fn eq(&self, other: &CStr) -> bool {
+        self.as_bytes() == other.to_bytes()
+    }
+}
+
+impl<const N: usize> PartialEq<str> for CStrBuf<N> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl<const N: usize> fmt::Write for CStrBuf<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s).map_err(|_| fmt::Error)
+    }
+}
+
+impl<const N: usize> fmt::Debug for CStrBuf<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_cstr(), f)
+    }
+}
+
+/// Checks the length returned by `strlcpy` or `strlcat` against the buffer size.
+fn check_truncation(len: size_t, size: usize) -> Result<(), Truncated> {
+    if (len as usize) < size { Ok(()) } else { Err(Truncated) }
+}
+
+/// A borrowed NUL-terminated string, searched with avr-libc's routines.
+#[derive(Copy, Clone)]
+pub struct CStrRef<'a> {
+    s: &'a CStr,
+}
+
+impl<'a> CStrRef<'a> {
+    /// Wraps a `CStr`.
+    pub fn new(s: &'a CStr) -> Self {
+        CStrRef { s }
+    }
+
+    /// Gets the wrapped `CStr`.
+    pub fn as_cstr(&self) -> &'a CStr {
+        self.s
+    }
+
+    /// Gets the bytes of the string, not including the terminator.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        unsafe { slice::from_raw_parts(self.s.as_ptr() as *const u8, self.len()) }
+    }
+
+    /// Gets the length of the string in bytes, using `strlen`.
+    pub fn len(&self) -> usize {
+        unsafe { strlen(self.s.as_ptr()) as usize }
+    }
+
+    /// Checks if the string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.as_bytes().is_empty()
+    }
+
+    /// Finds the first occurrence of `needle`, using `strstr`.
+    pub fn find(&self, needle: &CStr) -> Option<usize> {
+        self.offset_of(unsafe { strstr(self.s.as_ptr(), needle.as_ptr()) })
+    }
+
+    /// Finds the first occurrence of `needle`, ignoring ASCII case, using `strcasestr`.
+    pub fn find_ignore_ascii_case(&self, needle: &CStr) -> Option<usize> {
+        self.offset_of(unsafe { strcasestr(self.s.as_ptr(), needle.as_ptr()) })
+    }
+
+    /// Finds the first occurrence of a byte sequence, which may contain NUL bytes, using `memmem`.
+    pub fn find_bytes(&self, needle: &[u8]) -> Option<usize> {
+        let found = unsafe {
+            memmem(self.s.as_ptr() as *const c_void, self.len() as size_t,
+                   needle.as_ptr() as *const c_void, needle.len() as size_t)
+        };
+        self.offset_of(found as *mut c_char)
+    }
+
+    /// Checks if `needle` occurs in the string.
+    pub fn contains(&self, needle: &CStr) -> bool {
+        self.find(needle).is_some()
+    }
+
+    fn offset_of(&self, found: *mut c_char) -> Option<usize> {
+        if found.is_null() {
+            None
+        } else {
+            Some(found as usize - self.s.as_ptr() as usize)
+        }
+    }
+}
+
+impl<'a> From<&'a CStr> for CStrRef<'a> {
+    fn from(s: &'a CStr) -> Self {
+        CStrRef::new(s)
+    }
+}
+
+impl<'a> fmt::Debug for CStrRef<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.s, f)
+    }
+}
+
+/// An iterator over the tokens of a [`CStrBuf`](struct.CStrBuf.html), created by
+/// [`split`](struct.CStrBuf.html#method.split).
+pub struct Split<'a> {
+    rest: *mut c_char,
+    delimiters: &'a CStr,
+    _buf: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a> Iterator for Split<'a> {
+    type Item = &'a CStr;
+
+    fn next(&mut self) -> Option<&'a CStr> {
+        let token = unsafe { strsep(&mut self.rest, self.delimiters.as_ptr()) };
+
+        if token.is_null() {
+            None
+        } else {
+            Some(unsafe { CStr::from_ptr(token) })
+        }
+    }
+}
+
+/// An iterator over the non-empty tokens of a [`CStrBuf`](struct.CStrBuf.html),
+/// created by [`tokens`](struct.CStrBuf.html#method.tokens).
+pub struct Tokens<'a> {
+    start: *mut c_char,
+    save: *mut c_char,
+    delimiters: &'a CStr,
+    _buf: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = &'a CStr;
+
+    fn next(&mut self) -> Option<&'a CStr> {
+        // strtok_r takes the string on the first call, and null afterwards.
+        let token = unsafe { strtok_r(self.start, self.delimiters.as_ptr(), &mut self.save) };
+        self.start = ptr::null_mut();
+
+        if token.is_null() {
+            None
+        } else {
+            Some(unsafe { CStr::from_ptr(token) })
+        }
+    }
+}
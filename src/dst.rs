@@ -0,0 +1,277 @@
+//! Daylight saving time rules for `set_dst`.
+//!
+//! avr-libc decides whether daylight saving time is in effect by calling the
+//! function registered with `set_dst`. The examples it ships for the USA and
+//! the EU are `static inline` functions in `util/usa_dst.h` and `util/eu_dst.h`,
+//! which bindgen skips, so they are ported here as [`usa_dst`](fn.usa_dst.html)
+//! and [`eu_dst`](fn.eu_dst.html).
+//!
+//! Other regions can be described with a [`DstRule`](struct.DstRule.html):
+//!
+//! ```ignore
+//! // Australia (Sydney): first Sunday of October to first Sunday of April, at 2:00.
+//! let rule = DstRule::new(Transition::new(10, Week::First, 0, 2).unwrap(),
+//!                         Transition::new(4, Week::First, 0, 2).unwrap());
+//! time::set_zone(10 * ONE_HOUR as i32);
+//! dst::set_rule(rule);
+//! ```
+
+use bindings::{time_t, tm, ONE_HOUR};
+use bindings::{gmtime_r, month_length, week_of_month, set_dst};
+use rust_ctypes::c_int;
+use time::Tm;
+
+use core::ptr;
+
+/// The daylight saving time rule of the USA, as in `util/usa_dst.h`.
+///
+/// Daylight saving time starts on the second Sunday of March at 2:00 and ends
+/// on the first Sunday of November at 2:00, local time.
+///
+/// # Safety
+///
+/// Must only be called by avr-libc, with valid pointers.
+pub unsafe extern "C" fn usa_dst(timer: *const time_t, z: *mut i32) -> c_int {
+    const START_MONTH: u8 = 2;
+    const END_MONTH: u8 = 10;
+    const START_WEEK: u8 = 2;
+    const END_WEEK: u8 = 1;
+
+    let t = (*timer).wrapping_add(*z as time_t);
+    let mut tm = *Tm::EPOCH.as_raw();
+    gmtime_r(&t, &mut tm);
+
+    let month = tm.tm_mon as u8;
+    let day_of_week = tm.tm_wday as u8;
+    let week = week_of_month(&tm, 0);
+    let hour = tm.tm_hour as u8;
+
+    if month > START_MONTH && month < END_MONTH {
+        return ONE_HOUR as c_int;
+    }
+    if !(START_MONTH..=END_MONTH).contains(&month) {
+        return 0;
+    }
+
+    if month == START_MONTH {
+        if week < START_WEEK {
+            return 0;
+        }
+        if week > START_WEEK || day_of_week > 0 || hour >= 2 {
+            return ONE_HOUR as c_int;
+        }
+        return 0;
+    }
+
+    if week > END_WEEK {
+        return 0;
+    }
+    if week < END_WEEK {
+        return ONE_HOUR as c_int;
+    }
+    if day_of_week > 0 || hour >= 1 {
+        return 0;
+    }
+    ONE_HOUR as c_int
+}
+
+/// The daylight saving time rule of the EU, as in `util/eu_dst.h`.
+///
+/// Daylight saving time starts on the last Sunday of March and ends on the last
+/// Sunday of October.
+///
+/// Like the C version, this switches at 2:00 UTC, an hour later than the EU
+/// does. [`DstRule::EU`](struct.DstRule.html#associatedconstant.EU) switches at
+/// the correct 1:00 UTC.
+///
+/// # Safety
+///
+/// Must only be called by avr-libc, with valid pointers.
+pub unsafe extern "C" fn eu_dst(timer: *const time_t, _z: *mut i32) -> c_int {
+    const MARCH: u8 = 2;
+    const OCTOBER: u8 = 9;
+
+    let mut tm = *Tm::EPOCH.as_raw();
+    gmtime_r(timer, &mut tm);
+
+    let month = tm.tm_mon as u8;
+    let day_of_week = tm.tm_wday as u8;
+    let mday = tm.tm_mday as u8 - 1;
+    let hour = tm.tm_hour as u8;
+
+    if month > MARCH && month < OCTOBER {
+        return ONE_HOUR as c_int;
+    }
+    if !(MARCH..=OCTOBER).contains(&month) {
+        return 0;
+    }
+
+    // Find the day of the month of the last Sunday, counting from 0.
+    let first_sunday = (mday + 7 - day_of_week) % 7;
+    let last_sunday = first_sunday + 7 * ((31 - first_sunday) / 7);
+
+    let after_switch = mday > last_sunday || (mday == last_sunday && hour >= 2);
+    if (month == MARCH) == after_switch {
+        ONE_HOUR as c_int
+    } else {
+        0
+    }
+}
+
+/// The week of the month a transition happens in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Week {
+    /// Days 1 to 7.
+    First,
+    /// Days 8 to 14.
+    Second,
+    /// Days 15 to 21.
+    Third,
+    /// Days 22 to 28.
+    Fourth,
+    /// The last 7 days of the month.
+    Last,
+}
+
+/// The moment daylight saving time starts or ends, for example "the last
+/// Sunday of March at 1:00".
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Transition {
+    month: u8,
+    week: Week,
+    weekday: u8,
+    hour: u8,
+}
+
+impl Transition {
+    /// Creates a transition.
+    ///
+    /// `month` ranges from 1 to 12, `weekday` from 0 for Sunday to 6 for Saturday
+    /// and `hour` from 0 to 23. Returns `None` if any of them is out of range.
+    pub const fn new(month: u8, week: Week, weekday: u8, hour: u8) -> Option<Self> {
+        if month < 1 || month > 12 || weekday > 6 || hour > 23 {
+            None
+        } else {
+            Some(Transition { month, week, weekday, hour })
+        }
+    }
+
+    /// Gets the day of the month the transition happens on in a given year.
+    fn day(&self, year: i16, day: u8, weekday: u8) -> u8 {
+        // The weekday of the first of the month, from any date in it.
+        let first = (weekday + 35 - (day - 1) % 7) % 7;
+        let first_match = 1 + (self.weekday + 7 - first) % 7;
+
+        match self.week {
+            Week::First => first_match,
+            Week::Second => first_match + 7,
+            Week::Third => first_match + 14,
+            Week::Fourth => first_match + 21,
+            Week::Last => {
+                let length = unsafe { month_length(year, self.month) };
+                first_match + 7 * ((length - first_match) / 7)
+            },
+        }
+    }
+
+    /// Checks if the transition has happened by the given time of its year.
+    fn has_passed(&self, tm: &tm) -> bool {
+        let month = tm.tm_mon as u8 + 1;
+
+        if month != self.month {
+            return month > self.month;
+        }
+
+        let day = tm.tm_mday as u8;
+        let transition_day = self.day(tm.tm_year + 1900, day, tm.tm_wday as u8);
+        (day, tm.tm_hour as u8) >= (transition_day, self.hour)
+    }
+}
+
+/// A daylight saving time rule.
+///
+/// Daylight saving time is in effect from the `start` transition until the
+/// `end` transition, which may lie in the next year on the southern hemisphere.
+/// Transition hours are in local standard time, unless the rule is in UTC.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DstRule {
+    start: Transition,
+    end: Transition,
+    offset: i16,
+    utc: bool,
+}
+
+impl DstRule {
+    /// The rule of the USA: from the second Sunday of March at 2:00 until the
+    /// first Sunday of November at 1:00 standard time.
+    pub const USA: DstRule = DstRule {
+        start: Transition { month: 3, week: Week::Second, weekday: 0, hour: 2 },
+        end: Transition { month: 11, week: Week::First, weekday: 0, hour: 1 },
+        offset: ONE_HOUR as i16,
+        utc: false,
+    };
+
+    /// The rule of the EU: from the last Sunday of March until the last Sunday
+    /// of October, at 1:00 UTC.
+    pub const EU: DstRule = DstRule {
+        start: Transition { month: 3, week: Week::Last, weekday: 0, hour: 1 },
+        end: Transition { month: 10, week: Week::Last, weekday: 0, hour: 1 },
+        offset: ONE_HOUR as i16,
+        utc: true,
+    };
+
+    /// Creates a rule advancing the clock by one hour, with transitions in local standard time.
+    pub const fn new(start: Transition, end: Transition) -> Self {
+        DstRule { start, end, offset: ONE_HOUR as i16, utc: false }
+    }
+
+    /// Sets by how many seconds the clock is advanced.
+    pub const fn with_offset(self, offset_secs: i16) -> Self {
+        DstRule { offset: offset_secs, ..self }
+    }
+
+    /// Makes the transition hours UTC instead of local standard time.
+    pub const fn in_utc(self) -> Self {
+        DstRule { utc: true, ..self }
+    }
+
+    /// Gets the daylight saving offset in seconds at a point in time.
+    ///
+    /// `zone_offset` is the offset of local standard time east of UTC in seconds.
+    pub fn offset_at(&self, timestamp: time_t, zone_offset: i32) -> i16 {
+        let t = if self.utc { timestamp } else { timestamp.wrapping_add(zone_offset as time_t) };
+        let mut tm = *Tm::EPOCH.as_raw();
+        unsafe { gmtime_r(&t, &mut tm) };
+
+        let started = self.start.has_passed(&tm);
+        let ended = self.end.has_passed(&tm);
+        let in_effect = if self.start.month <= self.end.month {
+            started && !ended
+        } else {
+            started || !ended
+        };
+
+        if in_effect { self.offset } else { 0 }
+    }
+}
+
+/// The rule used by `rule_dst`.
+static mut RULE: DstRule = DstRule::USA;
+
+unsafe extern "C" fn rule_dst(timer: *const time_t, z: *mut i32) -> c_int {
+    let rule = *ptr::addr_of!(RULE);
+    rule.offset_at(*timer, *z) as c_int
+}
+
+/// Uses a rule for daylight saving time from now on.
+pub fn set_rule(rule: DstRule) {
+    unsafe {
+        *ptr::addr_of_mut!(RULE) = rule;
+        set_dst(Some(rule_dst));
+    }
+}
+
+/// Ignores daylight saving time from now on.
+pub fn clear_rule() {
+    unsafe { set_dst(None) }
+}
@@ -0,0 +1,293 @@
+//! Safe access to the on-chip EEPROM.
+//!
+//! Values are accessed through typed [`EepromCell`](struct.EepromCell.html)s, which
+//! are bounds-checked against the size of the EEPROM on the targeted device.
+//!
+//! Writes go through the `eeprom_update_*` routines by default, which only
+//! erase and write the bytes that actually changed, saving EEPROM wear.
+//!
+//! Rather than picking addresses by hand, variables can be declared with the
+//! [`eemem!`](../macro.eemem.html) macro, which lets the linker lay them out
+//! in the `.eeprom` section like `EEMEM` does in C.
+
+use bindings::{E2END, size_t};
+use bindings::{eeprom_read_byte, eeprom_read_word, eeprom_read_dword, eeprom_read_float, eeprom_read_block};
+use bindings::{eeprom_update_byte, eeprom_update_word, eeprom_update_dword, eeprom_update_float, eeprom_update_block};
+use bindings::{eeprom_write_byte, eeprom_write_word, eeprom_write_dword, eeprom_write_float, eeprom_write_block};
+use rust_ctypes::c_void;
+
+use core::marker::PhantomData;
+use core::{fmt, mem};
+
+/// The number of bytes of EEPROM on the targeted device.
+pub const EEPROM_SIZE: usize = E2END as usize + 1;
+
+/// An error returned when an access would reach past the end of the EEPROM.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct OutOfBounds;
+
+impl fmt::Display for OutOfBounds {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("access past the end of the eeprom")
+    }
+}
+
+/// An address in EEPROM.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EepromAddress(u16);
+
+impl EepromAddress {
+    /// Creates an address, checking that it lies inside the EEPROM.
+    pub fn new(address: u16) -> Result<Self, OutOfBounds> {
+        if (address as usize) < EEPROM_SIZE {
+            Ok(EepromAddress(address))
+        } else {
+            Err(OutOfBounds)
+        }
+    }
+
+    /// Gets the raw address.
+    pub fn get(self) -> u16 {
+        self.0
+    }
+
+    /// Checks that `len` bytes starting at this address lie inside the EEPROM.
+    fn check_len(self, len: usize) -> Result<(), OutOfBounds> {
+        if self.0 as usize + len <= EEPROM_SIZE {
+            Ok(())
+        } else {
+            Err(OutOfBounds)
+        }
+    }
+
+    fn as_ptr<T>(self) -> *mut T {
+        self.0 as usize as *mut T
+    }
+}
+
+/// A type which can be stored in EEPROM.
+///
+/// # Safety
+///
+/// Every bit pattern must be a valid value of the type, since EEPROM contents
+/// are not guaranteed to have been written by this program (an erased EEPROM
+/// reads back as all ones). Types must also be free of pointers and padding.
+///
+/// This holds for the primitive integers and `f32`, for arrays of them and for
+/// `#[repr(C)]` structs made up only of such fields.
+pub unsafe trait EepromData: Copy {
+    #[doc(hidden)]
+    unsafe fn read_at(address: EepromAddress) -> Self {
+        let mut value = mem::MaybeUninit::<Self>::uninit();
+        eeprom_read_block(value.as_mut_ptr() as *mut c_void,
+                          address.as_ptr(),
+                          mem::size_of::<Self>() as size_t);
+        value.assume_init()
+    }
+
+    #[doc(hidden)]
+    unsafe fn update_at(self, address: EepromAddress) {
+        eeprom_update_block(&self as *const Self as *const c_void,
+                            address.as_ptr(),
+                            mem::size_of::<Self>() as size_t);
+    }
+
+    #[doc(hidden)]
+    unsafe fn write_at(self, address: EepromAddress) {
+        eeprom_write_block(&self as *const Self as *const c_void,
+                           address.as_ptr(),
+                           mem::size_of::<Self>() as size_t);
+    }
+}
+
+macro_rules! impl_eeprom_data {
+    ($ty:ty, $raw:ty, $read:ident, $update:ident, $write:ident) => {
+        unsafe impl EepromData for $ty {
+            unsafe fn read_at(address: EepromAddress) -> Self {
+                $read(address.as_ptr()) as $ty
+            }
+
+            unsafe fn update_at(self, address: EepromAddress) {
+                $update(address.as_ptr(), self as $raw)
+            }
+
+            unsafe fn write_at(self, address: EepromAddress) {
+                $write(address.as_ptr(), self as $raw)
+            }
+        }
+    };
+}
+
+impl_eeprom_data!(u8, u8, eeprom_read_byte, eeprom_update_byte, eeprom_write_byte);
+impl_eeprom_data!(i8, u8, eeprom_read_byte, eeprom_update_byte, eeprom_write_byte);
+impl_eeprom_data!(u16, u16, eeprom_read_word, eeprom_update_word, eeprom_write_word);
+impl_eeprom_data!(i16, u16, eeprom_read_word, eeprom_update_word, eeprom_write_word);
+impl_eeprom_data!(u32, u32, eeprom_read_dword, eeprom_update_dword, eeprom_write_dword);
+impl_eeprom_data!(i32, u32, eeprom_read_dword, eeprom_update_dword, eeprom_write_dword);
+impl_eeprom_data!(f32, f32, eeprom_read_float, eeprom_update_float, eeprom_write_float);
+
+unsafe impl EepromData for u64 { }
+unsafe impl EepromData for i64 { }
+
+unsafe impl<T: EepromData, const N: usize> EepromData for [T; N] { }
+
+/// A typed value stored at a fixed address in EEPROM.
+#[derive(Debug, PartialEq, Eq)]
+pub struct EepromCell<T: EepromData> {
+    address: EepromAddress,
+    phantom: PhantomData<T>,
+}
+
+impl<T: EepromData> EepromCell<T> {
+    /// Creates a cell at the given address, checking that the whole value fits inside the EEPROM.
+    pub fn new(address: u16) -> Result<Self, OutOfBounds> {
+        let address = EepromAddress::new(address)?;
+        address.check_len(mem::size_of::<T>())?;
+
+        Ok(EepromCell { address, phantom: PhantomData })
+    }
+
+    /// Creates a cell without bounds checking.
+    ///
+    /// # Safety
+    ///
+    /// `address + size_of::<T>()` must not exceed [`EEPROM_SIZE`](constant.EEPROM_SIZE.html).
+    pub const unsafe fn new_unchecked(address: u16) -> Self {
+        EepromCell { address: EepromAddress(address), phantom: PhantomData }
+    }
+
+    /// Gets the address of the cell.
+    pub fn address(&self) -> EepromAddress {
+        self.address
+    }
+
+    /// Reads the value.
+    pub fn read(&self) -> T {
+        unsafe { T::read_at(self.address) }
+    }
+
+    /// Stores a value, only writing the bytes which differ from the current contents.
+    pub fn write(&self, value: T) {
+        unsafe { value.update_at(self.address) }
+    }
+
+    /// Stores a value, unconditionally erasing and writing every byte.
+    ///
+    /// Prefer [`write`](#method.write), which causes less EEPROM wear.
+    pub fn write_unconditionally(&self, value: T) {
+        unsafe { value.write_at(self.address) }
+    }
+}
+
+impl<T: EepromData> Clone for EepromCell<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: EepromData> Copy for EepromCell<T> { }
+
+/// A variable placed in the `.eeprom` section by [`eemem!`](../macro.eemem.html).
+///
+/// The variable itself must never be read directly, as it does not live in RAM.
+/// It is only used to get the EEPROM address assigned by the linker.
+pub struct EepromStatic<T: EepromData> {
+    storage: *const T,
+}
+
+// The pointer is never dereferenced, only converted to an address.
+unsafe impl<T: EepromData> Sync for EepromStatic<T> { }
+
+impl<T: EepromData> EepromStatic<T> {
+    #[doc(hidden)]
+    pub const unsafe fn new(storage: &'static T) -> Self {
+        EepromStatic { storage }
+    }
+
+    /// Gets a cell for accessing the variable.
+    pub fn cell(&self) -> EepromCell<T> {
+        // Pointers into the `.eeprom` section carry the EEPROM address in their lower 16 bits.
+        unsafe { EepromCell::new_unchecked(self.storage as usize as u16) }
+    }
+
+    /// Gets the address the linker assigned to the variable.
+    pub fn address(&self) -> EepromAddress {
+        self.cell().address()
+    }
+
+    /// Reads the value.
+    pub fn read(&self) -> T {
+        self.cell().read()
+    }
+
+    /// Stores a value, only writing the bytes which differ from the current contents.
+    pub fn write(&self, value: T) {
+        self.cell().write(value)
+    }
+
+    /// Stores a value, unconditionally erasing and writing every byte.
+    pub fn write_unconditionally(&self, value: T) {
+        self.cell().write_unconditionally(value)
+    }
+}
+
+/// Declares variables stored in EEPROM, like `EEMEM` does in C.
+///
+/// Each variable is placed in the `.eeprom` section, so its address is chosen by
+/// the linker and its initial value ends up in the EEPROM image, which can be
+/// extracted from the ELF file with
+/// `avr-objcopy -O ihex -j .eeprom --change-section-lma .eeprom=0 firmware.elf firmware.eep`.
+///
+/// The declared statics are [`EepromStatic`](eeprom/struct.EepromStatic.html)
+/// handles used to read and write the variables.
+///
+/// ```ignore
+/// eemem! {
+///     /// The number of times the device has booted.
+///     pub static BOOT_COUNT: u32 = 0;
+///     static CALIBRATION: [i16; 3] = [0; 3];
+/// }
+///
+/// BOOT_COUNT.write(BOOT_COUNT.read() + 1);
+/// ```
+#[macro_export]
+macro_rules! eemem {
+    ($($(#[$attr:meta])* $vis:vis static $name:ident : $ty:ty = $init:expr;)*) => {
+        $(
+            $(#[$attr])*
+            $vis static $name: $crate::eeprom::EepromStatic<$ty> = {
+                #[link_section = ".eeprom"]
+                #[used]
+                static STORAGE: $ty = $init;
+
+                unsafe { $crate::eeprom::EepromStatic::new(&STORAGE) }
+            };
+        )*
+    };
+}
+
+/// Reads raw bytes from EEPROM, starting at `address`.
+pub fn read_bytes(address: EepromAddress, buf: &mut [u8]) -> Result<(), OutOfBounds> {
+    address.check_len(buf.len())?;
+
+    unsafe {
+        eeprom_read_block(buf.as_mut_ptr() as *mut c_void,
+                          address.as_ptr(),
+                          buf.len() as size_t);
+    }
+    Ok(())
+}
+
+/// Writes raw bytes to EEPROM, starting at `address`.
+///
+/// Only the bytes which differ from the current contents are written.
+pub fn update_bytes(address: EepromAddress, bytes: &[u8]) -> Result<(), OutOfBounds> {
+    address.check_len(bytes.len())?;
+
+    unsafe {
+        eeprom_update_block(bytes.as_ptr() as *const c_void,
+                            address.as_ptr(),
+                            bytes.len() as size_t);
+    }
+    Ok(())
+}
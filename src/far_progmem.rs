@@ -0,0 +1,371 @@
+//! Data stored in program memory above the first 64 KiB of flash.
+//!
+//! Regular pointers are only 16 bits wide, so data past 64 KiB can only be
+//! reached through the 32-bit `uint_farptr_t` addresses taken by the `*_PF`
+//! routines. This module is only available on devices with more than 64 KiB
+//! of flash.
+//!
+//! Data is declared with [`far_progmem!`](../macro.far_progmem.html), and a
+//! [`FarProgMem`](struct.FarProgMem.html) or [`FarPStr`](struct.FarPStr.html)
+//! pointing to it is obtained with [`far!`](../macro.far.html).
+//!
+//! Computing a far address requires inline assembly, so crates using
+//! [`far!`](../macro.far.html) must enable `#![feature(asm_experimental_arch)]`.
+
+use bindings::{size_t, uint_farptr_t, memcpy_PF, memcmp_PF, strlen_PF};
+use progmem::{copy_str, cmp_str, eq_str, fmt_str, FlashStr};
+use rust_ctypes::{c_int, c_void};
+
+use core::cmp::{self, Ordering};
+use core::marker::PhantomData;
+use core::{fmt, mem, str};
+
+/// A value placed in far program memory by [`far_progmem!`](../macro.far_progmem.html).
+///
+/// It can only be accessed through the pointer returned by [`far!`](../macro.far.html).
+#[repr(transparent)]
+pub struct FarData<T> {
+    value: T,
+}
+
+// Flash is never written to at runtime.
+unsafe impl<T: Copy> Sync for FarData<T> { }
+
+impl<T: Copy> FarData<T> {
+    #[doc(hidden)]
+    pub const unsafe fn new(value: T) -> Self {
+        FarData { value }
+    }
+
+    #[doc(hidden)]
+    pub unsafe fn at(&'static self, address: uint_farptr_t) -> FarProgMem<T> {
+        FarProgMem::from_address(address)
+    }
+}
+
+/// A NUL-terminated string placed in far program memory by [`far_progmem!`](../macro.far_progmem.html).
+#[repr(transparent)]
+pub struct FarStr<const N: usize> {
+    bytes: [u8; N],
+}
+
+impl<const N: usize> FarStr<N> {
+    #[doc(hidden)]
+    pub const unsafe fn new(bytes: [u8; N]) -> Self {
+        FarStr { bytes }
+    }
+
+    #[doc(hidden)]
+    pub unsafe fn at(&'static self, address: uint_farptr_t) -> FarPStr {
+        FarPStr::from_address(address)
+    }
+}
+
+/// A pointer to a value in far program memory.
+pub struct FarProgMem<T> {
+    address: uint_farptr_t,
+    phantom: PhantomData<T>,
+}
+
+impl<T: Copy> FarProgMem<T> {
+    /// Creates a pointer from a far address.
+    ///
+    /// # Safety
+    ///
+    /// `address` must be the far address of a valid `T` in program memory.
+    pub const unsafe fn from_address(address: uint_farptr_t) -> Self {
+        FarProgMem { address, phantom: PhantomData }
+    }
+
+    /// Gets the far address of the value.
+    pub fn address(&self) -> uint_farptr_t {
+        self.address
+    }
+
+    /// Copies the value into RAM.
+    pub fn read(&self) -> T {
+        unsafe { read_value(self.address) }
+    }
+}
+
+impl<T: Copy, const N: usize> FarProgMem<[T; N]> {
+    /// Gets the number of elements in the array.
+    pub fn len(&self) -> usize {
+        N
+    }
+
+    /// Checks if the array is empty.
+    pub fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// Copies a single element into RAM.
+    pub fn get(&self, index: usize) -> Option<T> {
+        if index < N {
+            Some(unsafe { read_value(self.element_address(index)) })
+        } else {
+            None
+        }
+    }
+
+    /// Copies the elements starting at `start` into `buf`.
+    ///
+    /// Returns the number of elements copied, which is less than the length
+    /// of `buf` if the end of the array is reached.
+    pub fn copy_to(&self, start: usize, buf: &mut [T]) -> usize {
+        if start >= N {
+            return 0;
+        }
+        let count = cmp::min(buf.len(), N - start);
+
+        unsafe {
+            memcpy_PF(buf.as_mut_ptr() as *mut c_void,
+                      self.element_address(start),
+                      (count * mem::size_of::<T>()) as size_t);
+        }
+        count
+    }
+
+    /// Iterates over copies of the elements.
+    pub fn iter(&self) -> Iter<T, N> {
+        Iter { array: *self, index: 0 }
+    }
+
+    fn element_address(&self, index: usize) -> uint_farptr_t {
+        self.address + (index * mem::size_of::<T>()) as uint_farptr_t
+    }
+}
+
+impl<T> Clone for FarProgMem<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for FarProgMem<T> { }
+
+impl<T> fmt::Debug for FarProgMem<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FarProgMem({:#x})", self.address)
+    }
+}
+
+/// An iterator over the elements of an array in far program memory.
+pub struct Iter<T: Copy, const N: usize> {
+    array: FarProgMem<[T; N]>,
+    index: usize,
+}
+
+impl<T: Copy, const N: usize> Iterator for Iter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let item = self.array.get(self.index)?;
+        self.index += 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = N - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T: Copy, const N: usize> ExactSizeIterator for Iter<T, N> { }
+
+/// A pointer to a NUL-terminated string in far program memory.
+#[derive(Copy, Clone)]
+pub struct FarPStr {
+    address: uint_farptr_t,
+}
+
+impl FarPStr {
+    /// Creates a string from a far address.
+    ///
+    /// # Safety
+    ///
+    /// `address` must be the far address of a NUL-terminated, UTF-8 encoded
+    /// string in program memory.
+    pub const unsafe fn from_address(address: uint_farptr_t) -> Self {
+        FarPStr { address }
+    }
+
+    /// Gets the far address of the string.
+    pub fn address(&self) -> uint_farptr_t {
+        self.address
+    }
+
+    /// Gets the length of the string in bytes, not including the terminator.
+    pub fn len(&self) -> usize {
+        unsafe { strlen_PF(self.address) as usize }
+    }
+
+    /// Checks if the string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.bytes().next().is_none()
+    }
+
+    /// Iterates over the bytes of the string.
+    pub fn bytes(&self) -> Bytes {
+        Bytes { address: self.address }
+    }
+
+    /// Copies the string into a RAM buffer.
+    ///
+    /// Returns `None` if the buffer is too small to hold the string.
+    pub fn copy_to<'a>(&self, buf: &'a mut [u8]) -> Option<&'a str> {
+        copy_str(*self, buf)
+    }
+
+    /// Compares the string with a string in RAM.
+    pub fn cmp_str(&self, other: &str) -> Ordering {
+        cmp_str(*self, self.len(), other)
+    }
+}
+
+impl FlashStr for FarPStr {
+    fn len(self) -> usize {
+        FarPStr::len(&self)
+    }
+
+    unsafe fn copy(self, offset: usize, buf: &mut [u8]) {
+        memcpy_PF(buf.as_mut_ptr() as *mut c_void, self.address + offset as uint_farptr_t, buf.len() as size_t);
+    }
+
+    unsafe fn memcmp(self, other: &[u8]) -> c_int {
+        memcmp_PF(other.as_ptr() as *const c_void, self.address, other.len() as size_t)
+    }
+}
+
+impl PartialEq<str> for FarPStr {
+    fn eq(&self, other: &str) -> bool {
+        eq_str(*self, other)
+    }
+}
+
+impl<'a> PartialEq<&'a str> for FarPStr {
+    fn eq(&self, other: &&'a str) -> bool {
+        *self == **other
+    }
+}
+
+impl PartialOrd<str> for FarPStr {
+    fn partial_cmp(&self, other: &str) -> Option<Ordering> {
+        Some(self.cmp_str(other))
+    }
+}
+
+impl fmt::Display for FarPStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_str(*self, f)
+    }
+}
+
+impl fmt::Debug for FarPStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\"{}\"", self)
+    }
+}
+
+/// An iterator over the bytes of a [`FarPStr`](struct.FarPStr.html).
+pub struct Bytes {
+    address: uint_farptr_t,
+}
+
+impl Iterator for Bytes {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        let byte: u8 = unsafe { read_value(self.address) };
+        if byte == 0 {
+            None
+        } else {
+            self.address += 1;
+            Some(byte)
+        }
+    }
+}
+
+/// Copies a value out of far program memory.
+unsafe fn read_value<T: Copy>(address: uint_farptr_t) -> T {
+    let mut value = mem::MaybeUninit::<T>::uninit();
+    memcpy_PF(value.as_mut_ptr() as *mut c_void, address, mem::size_of::<T>() as size_t);
+    value.assume_init()
+}
+
+/// Gets the far address of a static, like `pgm_get_far_address` does in C.
+///
+/// Evaluates to a `uint_farptr_t`. The calling crate must enable
+/// `#![feature(asm_experimental_arch)]`.
+#[macro_export]
+macro_rules! far_address {
+    ($name:path) => {{
+        let lo: u8;
+        let hi: u8;
+        let hh: u8;
+
+        unsafe {
+            ::core::arch::asm!(
+                "ldi {lo}, lo8({var})",
+                "ldi {hi}, hi8({var})",
+                "ldi {hh}, hh8({var})",
+                var = sym $name,
+                lo = out(reg_upper) lo,
+                hi = out(reg_upper) hi,
+                hh = out(reg_upper) hh,
+                options(pure, nomem, nostack, preserves_flags),
+            );
+        }
+
+        (lo as u32) | ((hi as u32) << 8) | ((hh as u32) << 16)
+    }};
+}
+
+/// Gets a pointer to a static declared with [`far_progmem!`](macro.far_progmem.html).
+///
+/// Evaluates to a [`FarProgMem<T>`](far_progmem/struct.FarProgMem.html), or to a
+/// [`FarPStr`](far_progmem/struct.FarPStr.html) for strings. The calling crate
+/// must enable `#![feature(asm_experimental_arch)]`.
+#[macro_export]
+macro_rules! far {
+    ($name:path) => {
+        unsafe { $name.at($crate::far_address!($name)) }
+    };
+}
+
+/// Places data in program memory that may end up above the first 64 KiB of flash.
+///
+/// This works like [`progmem!`](macro.progmem.html), but the data is placed in
+/// the `.progmemx.data` section, which the linker puts after the code. Statics of
+/// type `PStr` are initialized from a string literal. The declared statics can
+/// only be accessed through the pointer returned by [`far!`](macro.far.html).
+///
+/// ```ignore
+/// far_progmem! {
+///     static LOOKUP: [u16; 4096] = [0; 4096];
+///     static BANNER: PStr = "Starting up";
+/// }
+///
+/// let value = far!(LOOKUP).get(1000);
+/// if far!(BANNER) == "Starting up" { /* ... */ }
+/// ```
+#[macro_export]
+macro_rules! far_progmem {
+    () => {};
+    ($(#[$attr:meta])* $vis:vis static $name:ident : PStr = $s:expr; $($rest:tt)*) => {
+        $(#[$attr])*
+        #[link_section = ".progmemx.data"]
+        $vis static $name: $crate::far_progmem::FarStr<{ $s.len() + 1 }> = unsafe {
+            $crate::far_progmem::FarStr::new($crate::progmem::nul_terminated($s))
+        };
+
+        $crate::far_progmem! { $($rest)* }
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident : $ty:ty = $init:expr; $($rest:tt)*) => {
+        $(#[$attr])*
+        #[link_section = ".progmemx.data"]
+        $vis static $name: $crate::far_progmem::FarData<$ty> = unsafe { $crate::far_progmem::FarData::new($init) };
+
+        $crate::far_progmem! { $($rest)* }
+    };
+}
@@ -0,0 +1,308 @@
+//! Fixed-point numbers with the layouts of avr-gcc's `_Fract` and `_Accum` types.
+//!
+//! avr-gcc supports the fixed-point types of ISO/IEC TR 18037, but Rust has no
+//! equivalent. Each type here wraps an integer holding the same bits as the C
+//! type, so values can be passed to and from C code unchanged.
+//!
+//! The `stdfix.h` functions such as `absk`, `roundk` and `countlsk` are
+//! compiler builtins rather than part of avr-libc, whose `stdfix-avrlibc.h` is
+//! empty, so they are implemented here as methods:
+//!
+//! ```ignore
+//! let gain = Accum::from_f32(1.75);
+//! let output = (gain * error).round(8);
+//! ```
+//!
+//! | Type                                     | C type                    | Integer bits | Fractional bits |
+//! |------------------------------------------|---------------------------|--------------|-----------------|
+//! | [`ShortFract`](struct.ShortFract.html)   | `short _Fract`            | 0            | 7               |
+//! | [`Fract`](struct.Fract.html)             | `_Fract`                  | 0            | 15              |
+//! | [`LongFract`](struct.LongFract.html)     | `long _Fract`             | 0            | 31              |
+//! | [`UShortFract`](struct.UShortFract.html) | `unsigned short _Fract`   | 0            | 8               |
+//! | [`UFract`](struct.UFract.html)           | `unsigned _Fract`         | 0            | 16              |
+//! | [`ULongFract`](struct.ULongFract.html)   | `unsigned long _Fract`    | 0            | 32              |
+//! | [`ShortAccum`](struct.ShortAccum.html)   | `short _Accum`            | 8            | 7               |
+//! | [`Accum`](struct.Accum.html)             | `_Accum`                  | 16           | 15              |
+//! | [`LongAccum`](struct.LongAccum.html)     | `long _Accum`             | 32           | 31              |
+//! | [`UShortAccum`](struct.UShortAccum.html) | `unsigned short _Accum`   | 8            | 8               |
+//! | [`UAccum`](struct.UAccum.html)           | `unsigned _Accum`         | 16           | 16              |
+//! | [`ULongAccum`](struct.ULongAccum.html)   | `unsigned long _Accum`    | 32           | 32              |
+//!
+//! The signed types have a sign bit in addition to the bits listed.
+//!
+//! Like the C types without `_Sat`, the operators wrap around on overflow.
+//! Multiplication and division round towards negative infinity, and division
+//! by zero panics.
+
+use core::convert::TryFrom;
+use core::fmt;
+use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+macro_rules! fixed_type {
+    ($(#[$attr:meta])* $name:ident($bits:ident, $wide:ident), $int_bits:expr, $frac_bits:expr) => {
+        $(#[$attr])*
+        #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[repr(transparent)]
+        pub struct $name($bits);
+
+        impl $name {
+            /// The number of integer bits, not counting the sign bit.
+            pub const INT_BITS: u32 = $int_bits;
+            /// The number of fractional bits.
+            pub const FRAC_BITS: u32 = $frac_bits;
+
+            /// Zero.
+            pub const ZERO: $name = $name(0);
+            /// The smallest value.
+            pub const MIN: $name = $name($bits::MIN);
+            /// The largest value.
+            pub const MAX: $name = $name($bits::MAX);
+            /// The smallest positive value.
+            pub const DELTA: $name = $name(1);
+
+            /// Creates a number from its bits, like the `bits` functions of `stdfix.h`.
+            pub const fn from_bits(bits: $bits) -> Self {
+                $name(bits)
+            }
+
+            /// Gets the bits of the number, like the `bits` functions of `stdfix.h`.
+            pub const fn to_bits(self) -> $bits {
+                self.0
+            }
+
+            /// Converts a float, rounding towards zero.
+            ///
+            /// Values out of range saturate, and NaN becomes zero.
+            pub fn from_f32(value: f32) -> Self {
+                $name((value * (1u64 << $frac_bits) as f32) as $bits)
+            }
+
+            /// Converts the number to the nearest float.
+            pub fn to_f32(self) -> f32 {
+                self.0 as f32 / (1u64 << $frac_bits) as f32
+            }
+
+            /// Rounds to `frac_bits` fractional bits, with halfway cases rounded up,
+            /// like the `round` functions of `stdfix.h`.
+            ///
+            /// The result saturates if rounding up overflows.
+            pub fn round(self, frac_bits: u32) -> Self {
+                if frac_bits >= $frac_bits {
+                    return self;
+                }
+
+                let shift = $frac_bits - frac_bits;
+                let half: $bits = 1 << (shift - 1);
+                let ones: $bits = !0;
+                let mask = ones.checked_shl(shift).unwrap_or(0);
+
+                match self.0.checked_add(half) {
+                    Some(bits) => $name(bits & mask),
+                    None => Self::MAX,
+                }
+            }
+
+            /// Adds two numbers, saturating at the bounds like `_Sat` types.
+            pub fn saturating_add(self, rhs: Self) -> Self {
+                $name(self.0.saturating_add(rhs.0))
+            }
+
+            /// Subtracts two numbers, saturating at the bounds like `_Sat` types.
+            pub fn saturating_sub(self, rhs: Self) -> Self {
+                $name(self.0.saturating_sub(rhs.0))
+            }
+
+            /// Multiplies two numbers, saturating at the bounds like `_Sat` types.
+            pub fn saturating_mul(self, rhs: Self) -> Self {
+                let product = (self.0 as $wide * rhs.0 as $wide) >> $frac_bits;
+                $name(product.clamp($bits::MIN as $wide, $bits::MAX as $wide) as $bits)
+            }
+        }
+
+        impl Add for $name {
+            type Output = $name;
+
+            fn add(self, rhs: $name) -> $name {
+                $name(self.0.wrapping_add(rhs.0))
+            }
+        }
+
+        impl Sub for $name {
+            type Output = $name;
+
+            fn sub(self, rhs: $name) -> $name {
+                $name(self.0.wrapping_sub(rhs.0))
+            }
+        }
+
+        impl Mul for $name {
+            type Output = $name;
+
+            fn mul(self, rhs: $name) -> $name {
+                $name(((self.0 as $wide * rhs.0 as $wide) >> $frac_bits) as $bits)
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, rhs: $name) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl SubAssign for $name {
+            fn sub_assign(&mut self, rhs: $name) {
+                *self = *self - rhs;
+            }
+        }
+
+        impl MulAssign for $name {
+            fn mul_assign(&mut self, rhs: $name) {
+                *self = *self * rhs;
+            }
+        }
+
+        impl DivAssign for $name {
+            fn div_assign(&mut self, rhs: $name) {
+                *self = *self / rhs;
+            }
+        }
+
+        impl fmt::Display for $name {
+            /// Formats the number through `f32`, so `long` types lose precision.
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                fmt::Display::fmt(&self.to_f32(), f)
+            }
+        }
+    };
+}
+
+macro_rules! signed_fixed_type {
+    ($(#[$attr:meta])* $name:ident($bits:ident, $wide:ident), $int_bits:expr, $frac_bits:expr) => {
+        fixed_type!($(#[$attr])* $name($bits, $wide), $int_bits, $frac_bits);
+
+        impl $name {
+            /// Gets the absolute value, saturating at [`MAX`](#associatedconstant.MAX)
+            /// like the `abs` functions of `stdfix.h`.
+            pub fn abs(self) -> Self {
+                $name(self.0.saturating_abs())
+            }
+
+            /// Counts how many places the number can be shifted left without
+            /// overflowing, like the `countls` functions of `stdfix.h`.
+            ///
+            /// Returns the total number of bits for zero.
+            pub fn countls(self) -> u32 {
+                match self.0 {
+                    0 => $bits::BITS,
+                    bits if bits < 0 => (!bits).leading_zeros() - 1,
+                    bits => bits.leading_zeros() - 1,
+                }
+            }
+        }
+
+        impl Neg for $name {
+            type Output = $name;
+
+            fn neg(self) -> $name {
+                $name(self.0.wrapping_neg())
+            }
+        }
+
+        impl Div for $name {
+            type Output = $name;
+
+            fn div(self, rhs: $name) -> $name {
+                let (n, d) = ((self.0 as $wide) << $frac_bits, rhs.0 as $wide);
+                // `div_euclid` only rounds towards negative infinity for positive divisors.
+                let q = if d < 0 { (-n).div_euclid(-d) } else { n.div_euclid(d) };
+                $name(q as $bits)
+            }
+        }
+    };
+}
+
+macro_rules! unsigned_fixed_type {
+    ($(#[$attr:meta])* $name:ident($bits:ident, $wide:ident), $int_bits:expr, $frac_bits:expr) => {
+        fixed_type!($(#[$attr])* $name($bits, $wide), $int_bits, $frac_bits);
+
+        impl $name {
+            /// Counts how many places the number can be shifted left without
+            /// overflowing, like the `countls` functions of `stdfix.h`.
+            ///
+            /// Returns the total number of bits for zero.
+            pub fn countls(self) -> u32 {
+                self.0.leading_zeros()
+            }
+        }
+
+        impl Div for $name {
+            type Output = $name;
+
+            fn div(self, rhs: $name) -> $name {
+                $name((((self.0 as $wide) << $frac_bits) / rhs.0 as $wide) as $bits)
+            }
+        }
+    };
+}
+
+macro_rules! accum_integers {
+    ($name:ident, $int:ident, $wide:ident, $frac_bits:expr) => {
+        impl $name {
+            /// Converts an integer, or returns `None` if it is out of range.
+            pub fn from_int(value: $int) -> Option<Self> {
+                let bits = (value as $wide).checked_mul(1 << $frac_bits)?;
+                TryFrom::try_from(bits).ok().map($name)
+            }
+
+            /// Gets the integer part, rounding towards negative infinity.
+            pub fn to_int(self) -> $int {
+                (self.0 >> $frac_bits) as $int
+            }
+        }
+    };
+}
+
+signed_fixed_type!(
+    /// A `short _Fract`, ranging from -1 to 1 - 2^-7.
+    ShortFract(i8, i16), 0, 7);
+signed_fixed_type!(
+    /// A `_Fract`, ranging from -1 to 1 - 2^-15.
+    Fract(i16, i32), 0, 15);
+signed_fixed_type!(
+    /// A `long _Fract`, ranging from -1 to 1 - 2^-31.
+    LongFract(i32, i64), 0, 31);
+unsigned_fixed_type!(
+    /// An `unsigned short _Fract`, ranging from 0 to 1 - 2^-8.
+    UShortFract(u8, u16), 0, 8);
+unsigned_fixed_type!(
+    /// An `unsigned _Fract`, ranging from 0 to 1 - 2^-16.
+    UFract(u16, u32), 0, 16);
+unsigned_fixed_type!(
+    /// An `unsigned long _Fract`, ranging from 0 to 1 - 2^-32.
+    ULongFract(u32, u64), 0, 32);
+
+signed_fixed_type!(
+    /// A `short _Accum`, ranging from -256 to 256 - 2^-7.
+    ShortAccum(i16, i32), 8, 7);
+signed_fixed_type!(
+    /// An `_Accum`, ranging from -65536 to 65536 - 2^-15.
+    Accum(i32, i64), 16, 15);
+signed_fixed_type!(
+    /// A `long _Accum`, ranging from -2^32 to 2^32 - 2^-31.
+    LongAccum(i64, i128), 32, 31);
+unsigned_fixed_type!(
+    /// An `unsigned short _Accum`, ranging from 0 to 256 - 2^-8.
+    UShortAccum(u16, u32), 8, 8);
+unsigned_fixed_type!(
+    /// An `unsigned _Accum`, ranging from 0 to 65536 - 2^-16.
+    UAccum(u32, u64), 16, 16);
+unsigned_fixed_type!(
+    /// An `unsigned long _Accum`, ranging from 0 to 2^32 - 2^-32.
+    ULongAccum(u64, u128), 32, 32);
+
+accum_integers!(ShortAccum, i16, i32, 7);
+accum_integers!(Accum, i32, i64, 15);
+accum_integers!(LongAccum, i64, i128, 31);
+accum_integers!(UShortAccum, u8, u32, 8);
+accum_integers!(UAccum, u16, u64, 16);
+accum_integers!(ULongAccum, u32, u128, 32);
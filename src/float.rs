@@ -0,0 +1,444 @@
+//! A `num_traits` float backed by avr-libc's math.
+//!
+//! `num_traits` implements `Float` for `f32` with the portable `libm` crate.
+//! [`AvrF32`](struct.AvrF32.html) wraps an `f32` and implements `Float` and
+//! `FloatCore` with the functions from [`libm`](../libm/index.html) instead,
+//! so crates which are generic over them get the faster avr-libc versions:
+//!
+//! ```ignore
+//! let mut pid = Pid::new(AvrF32(1.2), AvrF32(0.5), AvrF32(0.05));
+//! let output: f32 = pid.update(AvrF32(setpoint), AvrF32(measured)).into();
+//! ```
+
+use num_traits::{Num, NumCast, One, ToPrimitive, Zero};
+use num_traits::float::{Float, FloatCore};
+use libm::*;
+
+use core::num::FpCategory;
+use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Sub, SubAssign};
+use core::fmt;
+
+/// An `f32` which implements `num_traits::Float`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+#[repr(transparent)]
+pub struct AvrF32(pub f32);
+
+impl From<f32> for AvrF32 {
+    fn from(value: f32) -> Self {
+        AvrF32(value)
+    }
+}
+
+impl From<AvrF32> for f32 {
+    fn from(value: AvrF32) -> Self {
+        value.0
+    }
+}
+
+impl fmt::Display for AvrF32 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+macro_rules! impl_binary_op {
+    ($($op:ident::$method:ident, $assign_op:ident::$assign_method:ident, $f:expr;)*) => {
+        $(
+            impl $op for AvrF32 {
+                type Output = AvrF32;
+
+                fn $method(self, rhs: AvrF32) -> AvrF32 {
+                    let f: fn(f32, f32) -> f32 = $f;
+                    AvrF32(f(self.0, rhs.0))
+                }
+            }
+
+            impl $assign_op for AvrF32 {
+                fn $assign_method(&mut self, rhs: AvrF32) {
+                    *self = $op::$method(*self, rhs);
+                }
+            }
+        )*
+    };
+}
+
+impl_binary_op! {
+    Add::add, AddAssign::add_assign, |a, b| a + b;
+    Sub::sub, SubAssign::sub_assign, |a, b| a - b;
+    Mul::mul, MulAssign::mul_assign, |a, b| a * b;
+    Div::div, DivAssign::div_assign, |a, b| a / b;
+    Rem::rem, RemAssign::rem_assign, fmodf;
+}
+
+impl Neg for AvrF32 {
+    type Output = AvrF32;
+
+    fn neg(self) -> AvrF32 {
+        AvrF32(-self.0)
+    }
+}
+
+impl Zero for AvrF32 {
+    fn zero() -> Self {
+        AvrF32(0.0)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0 == 0.0
+    }
+}
+
+impl One for AvrF32 {
+    fn one() -> Self {
+        AvrF32(1.0)
+    }
+}
+
+impl Num for AvrF32 {
+    type FromStrRadixErr = <f32 as Num>::FromStrRadixErr;
+
+    fn from_str_radix(s: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        f32::from_str_radix(s, radix).map(AvrF32)
+    }
+}
+
+impl ToPrimitive for AvrF32 {
+    fn to_i64(&self) -> Option<i64> {
+        self.0.to_i64()
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        self.0.to_u64()
+    }
+
+    fn to_f32(&self) -> Option<f32> {
+        Some(self.0)
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        Some(self.0 as f64)
+    }
+}
+
+impl NumCast for AvrF32 {
+    fn from<T: ToPrimitive>(n: T) -> Option<Self> {
+        n.to_f32().map(AvrF32)
+    }
+}
+
+impl FloatCore for AvrF32 {
+    fn infinity() -> Self {
+        AvrF32(f32::INFINITY)
+    }
+
+    fn neg_infinity() -> Self {
+        AvrF32(f32::NEG_INFINITY)
+    }
+
+    fn nan() -> Self {
+        AvrF32(f32::NAN)
+    }
+
+    fn neg_zero() -> Self {
+        AvrF32(-0.0)
+    }
+
+    fn min_value() -> Self {
+        AvrF32(f32::MIN)
+    }
+
+    fn min_positive_value() -> Self {
+        AvrF32(f32::MIN_POSITIVE)
+    }
+
+    fn epsilon() -> Self {
+        AvrF32(f32::EPSILON)
+    }
+
+    fn max_value() -> Self {
+        AvrF32(f32::MAX)
+    }
+
+    fn classify(self) -> FpCategory {
+        FloatCore::classify(self.0)
+    }
+
+    fn to_degrees(self) -> Self {
+        AvrF32(FloatCore::to_degrees(self.0))
+    }
+
+    fn to_radians(self) -> Self {
+        AvrF32(FloatCore::to_radians(self.0))
+    }
+
+    fn integer_decode(self) -> (u64, i16, i8) {
+        FloatCore::integer_decode(self.0)
+    }
+
+    fn floor(self) -> Self {
+        AvrF32(floorf(self.0))
+    }
+
+    fn ceil(self) -> Self {
+        AvrF32(ceilf(self.0))
+    }
+
+    fn round(self) -> Self {
+        AvrF32(roundf(self.0))
+    }
+
+    fn trunc(self) -> Self {
+        AvrF32(truncf(self.0))
+    }
+
+    fn fract(self) -> Self {
+        AvrF32(modff(self.0).0)
+    }
+
+    fn abs(self) -> Self {
+        AvrF32(fabsf(self.0))
+    }
+
+    fn powi(self, n: i32) -> Self {
+        AvrF32(powf(self.0, n as f32))
+    }
+}
+
+impl Float for AvrF32 {
+    fn nan() -> Self {
+        AvrF32(f32::NAN)
+    }
+
+    fn infinity() -> Self {
+        AvrF32(f32::INFINITY)
+    }
+
+    fn neg_infinity() -> Self {
+        AvrF32(f32::NEG_INFINITY)
+    }
+
+    fn neg_zero() -> Self {
+        AvrF32(-0.0)
+    }
+
+    fn min_value() -> Self {
+        AvrF32(f32::MIN)
+    }
+
+    fn min_positive_value() -> Self {
+        AvrF32(f32::MIN_POSITIVE)
+    }
+
+    fn epsilon() -> Self {
+        AvrF32(f32::EPSILON)
+    }
+
+    fn max_value() -> Self {
+        AvrF32(f32::MAX)
+    }
+
+    fn is_nan(self) -> bool {
+        FloatCore::is_nan(self.0)
+    }
+
+    fn is_infinite(self) -> bool {
+        FloatCore::is_infinite(self.0)
+    }
+
+    fn is_finite(self) -> bool {
+        FloatCore::is_finite(self.0)
+    }
+
+    fn is_normal(self) -> bool {
+        FloatCore::is_normal(self.0)
+    }
+
+    fn classify(self) -> FpCategory {
+        FloatCore::classify(self.0)
+    }
+
+    fn floor(self) -> Self {
+        AvrF32(floorf(self.0))
+    }
+
+    fn ceil(self) -> Self {
+        AvrF32(ceilf(self.0))
+    }
+
+    fn round(self) -> Self {
+        AvrF32(roundf(self.0))
+    }
+
+    fn trunc(self) -> Self {
+        AvrF32(truncf(self.0))
+    }
+
+    fn fract(self) -> Self {
+        AvrF32(modff(self.0).0)
+    }
+
+    fn abs(self) -> Self {
+        AvrF32(fabsf(self.0))
+    }
+
+    fn signum(self) -> Self {
+        AvrF32(FloatCore::signum(self.0))
+    }
+
+    fn is_sign_positive(self) -> bool {
+        FloatCore::is_sign_positive(self.0)
+    }
+
+    fn is_sign_negative(self) -> bool {
+        FloatCore::is_sign_negative(self.0)
+    }
+
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        AvrF32(fmaf(self.0, a.0, b.0))
+    }
+
+    fn recip(self) -> Self {
+        AvrF32(1.0 / self.0)
+    }
+
+    fn powi(self, n: i32) -> Self {
+        AvrF32(powf(self.0, n as f32))
+    }
+
+    fn powf(self, n: Self) -> Self {
+        AvrF32(powf(self.0, n.0))
+    }
+
+    fn sqrt(self) -> Self {
+        AvrF32(sqrtf(self.0))
+    }
+
+    fn exp(self) -> Self {
+        AvrF32(expf(self.0))
+    }
+
+    fn exp2(self) -> Self {
+        AvrF32(exp2f(self.0))
+    }
+
+    fn ln(self) -> Self {
+        AvrF32(logf(self.0))
+    }
+
+    fn log(self, base: Self) -> Self {
+        AvrF32(logf(self.0) / logf(base.0))
+    }
+
+    fn log2(self) -> Self {
+        AvrF32(log2f(self.0))
+    }
+
+    fn log10(self) -> Self {
+        AvrF32(log10f(self.0))
+    }
+
+    fn to_degrees(self) -> Self {
+        AvrF32(FloatCore::to_degrees(self.0))
+    }
+
+    fn to_radians(self) -> Self {
+        AvrF32(FloatCore::to_radians(self.0))
+    }
+
+    fn max(self, other: Self) -> Self {
+        AvrF32(fmaxf(self.0, other.0))
+    }
+
+    fn min(self, other: Self) -> Self {
+        AvrF32(fminf(self.0, other.0))
+    }
+
+    fn clamp(self, min: Self, max: Self) -> Self {
+        AvrF32(FloatCore::clamp(self.0, min.0, max.0))
+    }
+
+    fn abs_sub(self, other: Self) -> Self {
+        AvrF32(fdimf(self.0, other.0))
+    }
+
+    fn cbrt(self) -> Self {
+        AvrF32(cbrtf(self.0))
+    }
+
+    fn hypot(self, other: Self) -> Self {
+        AvrF32(hypotf(self.0, other.0))
+    }
+
+    fn sin(self) -> Self {
+        AvrF32(sinf(self.0))
+    }
+
+    fn cos(self) -> Self {
+        AvrF32(cosf(self.0))
+    }
+
+    fn tan(self) -> Self {
+        AvrF32(tanf(self.0))
+    }
+
+    fn asin(self) -> Self {
+        AvrF32(asinf(self.0))
+    }
+
+    fn acos(self) -> Self {
+        AvrF32(acosf(self.0))
+    }
+
+    fn atan(self) -> Self {
+        AvrF32(atanf(self.0))
+    }
+
+    fn atan2(self, other: Self) -> Self {
+        AvrF32(atan2f(self.0, other.0))
+    }
+
+    fn sin_cos(self) -> (Self, Self) {
+        let (sin, cos) = sincosf(self.0);
+        (AvrF32(sin), AvrF32(cos))
+    }
+
+    fn exp_m1(self) -> Self {
+        AvrF32(expm1f(self.0))
+    }
+
+    fn ln_1p(self) -> Self {
+        AvrF32(log1pf(self.0))
+    }
+
+    fn sinh(self) -> Self {
+        AvrF32(sinhf(self.0))
+    }
+
+    fn cosh(self) -> Self {
+        AvrF32(coshf(self.0))
+    }
+
+    fn tanh(self) -> Self {
+        AvrF32(tanhf(self.0))
+    }
+
+    fn asinh(self) -> Self {
+        AvrF32(asinhf(self.0))
+    }
+
+    fn acosh(self) -> Self {
+        AvrF32(acoshf(self.0))
+    }
+
+    fn atanh(self) -> Self {
+        AvrF32(atanhf(self.0))
+    }
+
+    fn integer_decode(self) -> (u64, i16, i8) {
+        FloatCore::integer_decode(self.0)
+    }
+
+    fn copysign(self, sign: Self) -> Self {
+        AvrF32(copysignf(self.0, sign.0))
+    }
+}
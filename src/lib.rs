@@ -15,7 +15,38 @@
 // avr-rust/libc#1
 #![allow(overflowing_literals)]
 
+#[cfg(feature = "ufmt")]
+extern crate ufmt_write;
+#[cfg(feature = "num-traits")]
+extern crate num_traits;
+
 pub use self::bindings::*;
 
-mod bindings;
+/// The bindings generated by the build script for the targeted microcontroller.
+mod bindings {
+    include!(env!("AVR_LIBC_BINDINGS"));
+}
 pub mod rust_ctypes;
+pub mod astro;
+pub mod calendar;
+pub mod cstr;
+pub mod dst;
+pub mod fixed;
+pub mod libm;
+pub mod math;
+pub mod memory;
+pub mod numfmt;
+pub mod printf;
+pub mod progmem;
+pub mod scanf;
+pub mod stdio;
+pub mod time;
+
+#[cfg(feature = "alloc")]
+pub mod allocator;
+#[cfg(avr_libc_eeprom)]
+pub mod eeprom;
+#[cfg(feature = "num-traits")]
+pub mod float;
+#[cfg(avr_libc_far_progmem)]
+pub mod far_progmem;
@@ -0,0 +1,1282 @@
+//! The math functions of the [`libm`](https://docs.rs/libm) crate, implemented by avr-libc.
+//!
+//! The functions have the same names and signatures as in `libm` 0.2, so code
+//! written against it can switch over by changing its imports. Dependencies
+//! which use `libm` can be switched over as well by patching it with the `libm`
+//! crate of this repository, which re-exports this module.
+//!
+//! Functions without a counterpart in avr-libc are built from the ones which
+//! have one, and may be less accurate than in `libm`. As `double` is 32 bits
+//! wide on AVR, the `f64` functions are computed in single precision, except
+//! for those which only work on the representation of the number such as
+//! [`floor`](fn.floor.html), [`frexp`](fn.frexp.html) or [`nextafter`](fn.nextafter.html).
+//!
+//! The `f16` and `f128` functions, which `libm` only has with unstable
+//! features, and its generic `Libm` helper are left out.
+
+use bindings;
+use math::AvrF32Ext;
+
+use core::f32::consts::{FRAC_1_SQRT_2, FRAC_2_PI, FRAC_2_SQRT_PI, LN_2, LOG2_E, PI};
+
+/// Computes the arccosine.
+pub fn acosf(x: f32) -> f32 {
+    AvrF32Ext::acos(x)
+}
+
+/// Computes the inverse hyperbolic cosine.
+pub fn acoshf(x: f32) -> f32 {
+    if x >= 4096.0 {
+        // `x * x` would overflow, and `acosh(x) = ln(2x)` to single precision.
+        logf(x) + LN_2
+    } else {
+        let t = x - 1.0;
+        log1pf(t + AvrF32Ext::sqrt(2.0 * t + t * t))
+    }
+}
+
+/// Computes the arcsine.
+pub fn asinf(x: f32) -> f32 {
+    AvrF32Ext::asin(x)
+}
+
+/// Computes the inverse hyperbolic sine.
+pub fn asinhf(x: f32) -> f32 {
+    let a = fabsf(x);
+    let y = if a >= 4096.0 {
+        logf(a) + LN_2
+    } else {
+        log1pf(a + a * a / (1.0 + AvrF32Ext::sqrt(a * a + 1.0)))
+    };
+    copysignf(y, x)
+}
+
+/// Computes the arctangent.
+pub fn atanf(x: f32) -> f32 {
+    AvrF32Ext::atan(x)
+}
+
+/// Computes the four quadrant arctangent of `y` and `x`.
+pub fn atan2f(y: f32, x: f32) -> f32 {
+    AvrF32Ext::atan2(y, x)
+}
+
+/// Computes the inverse hyperbolic tangent.
+pub fn atanhf(x: f32) -> f32 {
+    0.5 * log1pf(2.0 * x / (1.0 - x))
+}
+
+/// Computes the cube root.
+pub fn cbrtf(x: f32) -> f32 {
+    AvrF32Ext::cbrt(x)
+}
+
+/// Rounds towards positive infinity.
+pub fn ceilf(x: f32) -> f32 {
+    AvrF32Ext::ceil(x)
+}
+
+/// Gets `x` with the sign of `y`.
+pub fn copysignf(x: f32, y: f32) -> f32 {
+    f32::from_bits((x.to_bits() & !(1 << 31)) | (y.to_bits() & (1 << 31)))
+}
+
+/// Computes the cosine.
+pub fn cosf(x: f32) -> f32 {
+    AvrF32Ext::cos(x)
+}
+
+/// Computes the hyperbolic cosine.
+pub fn coshf(x: f32) -> f32 {
+    AvrF32Ext::cosh(x)
+}
+
+/// Computes the error function.
+pub fn erff(x: f32) -> f32 {
+    if fabsf(x) < 0.5 {
+        // Taylor series, as `1 - erfc(x)` cancels out near zero.
+        let y = x * x;
+        let series = 1.0 / 9360.0;
+        let series = -1.0 / 1320.0 + y * series;
+        let series = 1.0 / 216.0 + y * series;
+        let series = -1.0 / 42.0 + y * series;
+        let series = 0.1 + y * series;
+        let series = -1.0 / 3.0 + y * series;
+        FRAC_2_SQRT_PI * (x + x * y * series)
+    } else {
+        1.0 - erfcf(x)
+    }
+}
+
+/// Computes the complementary error function, `1 - erf(x)`.
+pub fn erfcf(x: f32) -> f32 {
+    // Chebyshev approximation from Numerical Recipes, with a relative error
+    // below 1.2e-7.
+    let z = fabsf(x);
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let p = 0.170_872_77;
+    let p = -0.822_152_23 + t * p;
+    let p = 1.488_515_9 + t * p;
+    let p = -1.135_204 + t * p;
+    let p = 0.278_868_07 + t * p;
+    let p = -0.186_288_06 + t * p;
+    let p = 0.096_784_18 + t * p;
+    let p = 0.374_091_96 + t * p;
+    let p = 1.000_023_7 + t * p;
+    let p = -1.265_512_2 + t * p;
+    let y = t * expf(-z * z + p);
+
+    if x >= 0.0 {
+        y
+    } else {
+        2.0 - y
+    }
+}
+
+/// Computes `e^x`.
+pub fn expf(x: f32) -> f32 {
+    AvrF32Ext::exp(x)
+}
+
+/// Computes `2^x`.
+pub fn exp2f(x: f32) -> f32 {
+    AvrF32Ext::powf(2.0, x)
+}
+
+/// Computes `10^x`.
+pub fn exp10f(x: f32) -> f32 {
+    AvrF32Ext::powf(10.0, x)
+}
+
+/// Computes `e^x - 1`.
+pub fn expm1f(x: f32) -> f32 {
+    let u = expf(x);
+
+    // Near zero, `u - 1` cancels out most digits. Dividing by `ln(u)` instead
+    // of `x` compensates for the rounding of `u`.
+    if u == 1.0 {
+        x
+    } else if u - 1.0 == -1.0 {
+        -1.0
+    } else if u == f32::INFINITY {
+        u
+    } else {
+        (u - 1.0) * (x / logf(u))
+    }
+}
+
+/// Gets the absolute value.
+pub fn fabsf(x: f32) -> f32 {
+    unsafe { bindings::fabs(x) }
+}
+
+/// Computes `x - y` if it is positive, and zero otherwise.
+pub fn fdimf(x: f32, y: f32) -> f32 {
+    AvrF32Ext::positive_diff(x, y)
+}
+
+/// Rounds towards negative infinity.
+pub fn floorf(x: f32) -> f32 {
+    AvrF32Ext::floor(x)
+}
+
+/// Computes `x * y + z` with a single rounding.
+pub fn fmaf(x: f32, y: f32, z: f32) -> f32 {
+    AvrF32Ext::mul_add(x, y, z)
+}
+
+/// Gets the larger number, ignoring NaN.
+pub fn fmaxf(x: f32, y: f32) -> f32 {
+    unsafe { bindings::fmax(x, y) }
+}
+
+/// Gets the larger number, propagating NaN and ordering `-0.0` below `0.0`.
+pub fn fmaximumf(x: f32, y: f32) -> f32 {
+    if x.is_nan() {
+        x
+    } else if y.is_nan() {
+        y
+    } else if x > y || (y.to_bits() == (-0.0f32).to_bits() && x.is_sign_positive()) {
+        x
+    } else {
+        y
+    }
+}
+
+/// Gets the larger number, ignoring NaN and ordering `-0.0` below `0.0`.
+pub fn fmaximum_numf(x: f32, y: f32) -> f32 {
+    if x > y || y.is_nan() {
+        x
+    } else if y > x || x.is_nan() || x.is_sign_negative() {
+        y
+    } else {
+        x
+    }
+}
+
+/// Gets the smaller number, ignoring NaN.
+pub fn fminf(x: f32, y: f32) -> f32 {
+    unsafe { bindings::fmin(x, y) }
+}
+
+/// Gets the smaller number, propagating NaN and ordering `-0.0` below `0.0`.
+pub fn fminimumf(x: f32, y: f32) -> f32 {
+    if x.is_nan() {
+        x
+    } else if y.is_nan() {
+        y
+    } else if x < y || (x.to_bits() == (-0.0f32).to_bits() && y.is_sign_positive()) {
+        x
+    } else {
+        y
+    }
+}
+
+/// Gets the smaller number, ignoring NaN and ordering `-0.0` below `0.0`.
+pub fn fminimum_numf(x: f32, y: f32) -> f32 {
+    if x > y || x.is_nan() {
+        y
+    } else if y > x || y.is_nan() || x.is_sign_negative() {
+        x
+    } else {
+        y
+    }
+}
+
+/// Computes the remainder of `x / y`, with the sign of `x`.
+pub fn fmodf(x: f32, y: f32) -> f32 {
+    AvrF32Ext::fmod(x, y)
+}
+
+/// Splits a number into a mantissa from 0.5 to 1 and a power of two.
+pub fn frexpf(x: f32) -> (f32, i32) {
+    let (mantissa, exp) = AvrF32Ext::frexp(x);
+    (mantissa, exp as i32)
+}
+
+/// Computes the length of the hypotenuse of a right triangle.
+pub fn hypotf(x: f32, y: f32) -> f32 {
+    AvrF32Ext::hypot(x, y)
+}
+
+/// Gets the exponent of a number, `i32::MIN` for zero and NaN and
+/// `i32::MAX` for infinity.
+pub fn ilogbf(x: f32) -> i32 {
+    let bits = x.to_bits();
+    let exp = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits << 9;
+
+    match exp {
+        0 if mantissa == 0 => i32::MIN,
+        0 => -127 - mantissa.leading_zeros() as i32,
+        0xff if mantissa == 0 => i32::MAX,
+        0xff => i32::MIN,
+        _ => exp - 127,
+    }
+}
+
+/// Computes the Bessel function of the first kind of order 0.
+pub fn j0f(x: f32) -> f32 {
+    let ax = fabsf(x);
+
+    if ax < 8.0 {
+        // Rational approximation from Numerical Recipes, as are the others below.
+        let y = x * x;
+        let p = -184.905_24;
+        let p = 77_392.33 + y * p;
+        let p = -11_214_424.18 + y * p;
+        let p = 651_619_640.7 + y * p;
+        let p = -13_362_590_354.0 + y * p;
+        let p = 57_568_490_574.0 + y * p;
+        let q = 1.0;
+        let q = 267.853_27 + y * q;
+        let q = 59_272.65 + y * q;
+        let q = 9_494_680.718 + y * q;
+        let q = 1_029_532_985.0 + y * q;
+        let q = 57_568_490_411.0 + y * q;
+        p / q
+    } else if ax == f32::INFINITY {
+        0.0
+    } else {
+        bessel_asymptotic(ax, false).0
+    }
+}
+
+/// Computes the Bessel function of the first kind of order 1.
+pub fn j1f(x: f32) -> f32 {
+    let ax = fabsf(x);
+
+    if ax < 8.0 {
+        let y = x * x;
+        let p = -30.160_366;
+        let p = 15_704.482 + y * p;
+        let p = -2_972_611.5 + y * p;
+        let p = 242_396_853.1 + y * p;
+        let p = -7_895_059_235.0 + y * p;
+        let p = 72_362_614_232.0 + y * p;
+        let q = 1.0;
+        let q = 376.999_15 + y * q;
+        let q = 99_447.44 + y * q;
+        let q = 18_583_304.74 + y * q;
+        let q = 2_300_535_178.0 + y * q;
+        let q = 144_725_228_442.0 + y * q;
+        x * p / q
+    } else if ax == f32::INFINITY {
+        copysignf(0.0, x)
+    } else if x < 0.0 {
+        -bessel_asymptotic(ax, true).0
+    } else {
+        bessel_asymptotic(ax, true).0
+    }
+}
+
+/// Computes the Bessel function of the first kind of order `n`.
+pub fn jnf(n: i32, x: f32) -> f32 {
+    if x.is_nan() {
+        return x;
+    }
+
+    // `J(-n, x) = J(n, -x) = (-1)^n J(n, x)`
+    let negate = n & 1 != 0 && (n < 0) != x.is_sign_negative();
+    let n = n.unsigned_abs();
+    let ax = fabsf(x);
+    let y = match n {
+        0 => return j0f(x),
+        1 => j1f(ax),
+        _ if ax == 0.0 || ax == f32::INFINITY => 0.0,
+        _ => bessel_jn(n, ax),
+    };
+
+    if negate {
+        -y
+    } else {
+        y
+    }
+}
+
+/// Multiplies a number by `2^n`.
+pub fn ldexpf(x: f32, n: i32) -> f32 {
+    // Anything beyond this range overflows or underflows anyway.
+    AvrF32Ext::ldexp(x, n.clamp(i16::MIN as i32, i16::MAX as i32) as i16)
+}
+
+/// Computes the natural logarithm of the absolute value of the gamma function.
+pub fn lgammaf(x: f32) -> f32 {
+    lgammaf_r(x).0
+}
+
+/// Computes the natural logarithm of the absolute value of the gamma function,
+/// along with the sign of the gamma function.
+pub fn lgammaf_r(x: f32) -> (f32, i32) {
+    if x.is_nan() {
+        return (x, 1);
+    }
+
+    if x.is_infinite() {
+        return (f32::INFINITY, 1);
+    }
+
+    // `Γ(x) = 1 / x` to single precision, which also covers zero.
+    if fabsf(x) < 1e-8 {
+        return (-logf(fabsf(x)), if x.is_sign_negative() { -1 } else { 1 });
+    }
+
+    if x < 0.0 {
+        // Reflection formula, `Γ(x) Γ(1 - x) = π / sin(π x)`, with
+        // `Γ(1 - x) = -x Γ(-x)`.
+        let s = sin_pi(x);
+        if s == 0.0 {
+            return (f32::INFINITY, 1);
+        }
+
+        let y = logf(PI / fabsf(s * x)) - ln_gamma(-x);
+        return (y, if s < 0.0 { -1 } else { 1 });
+    }
+
+    (ln_gamma(x), 1)
+}
+
+/// Computes the natural logarithm.
+pub fn logf(x: f32) -> f32 {
+    AvrF32Ext::ln(x)
+}
+
+/// Computes the base 10 logarithm.
+pub fn log10f(x: f32) -> f32 {
+    AvrF32Ext::log10(x)
+}
+
+/// Computes the natural logarithm of `1 + x`.
+pub fn log1pf(x: f32) -> f32 {
+    let u = 1.0 + x;
+
+    // Compensates for the rounding of `1 + x` when `x` is small.
+    if u == 1.0 || u == f32::INFINITY {
+        x
+    } else {
+        logf(u) * (x / (u - 1.0))
+    }
+}
+
+/// Computes the base 2 logarithm.
+pub fn log2f(x: f32) -> f32 {
+    AvrF32Ext::ln(x) * LOG2_E
+}
+
+/// Splits a number into its fractional and integral parts.
+pub fn modff(x: f32) -> (f32, f32) {
+    AvrF32Ext::modf(x)
+}
+
+/// Gets the next representable number after `x` in the direction of `y`.
+pub fn nextafterf(x: f32, y: f32) -> f32 {
+    if x.is_nan() || y.is_nan() {
+        return x + y;
+    }
+
+    let (ux, uy) = (x.to_bits(), y.to_bits());
+    if ux == uy {
+        return y;
+    }
+
+    let (ax, ay) = (ux & !(1 << 31), uy & !(1 << 31));
+    let next = if ax == 0 {
+        if ay == 0 {
+            return y;
+        }
+        (uy & (1 << 31)) | 1
+    } else if ax > ay || (ux ^ uy) & (1 << 31) != 0 {
+        ux - 1
+    } else {
+        ux + 1
+    };
+
+    f32::from_bits(next)
+}
+
+/// Raises `x` to the power of `y`.
+pub fn powf(x: f32, y: f32) -> f32 {
+    AvrF32Ext::powf(x, y)
+}
+
+/// Computes `x - n * y`, where `n` is `x / y` rounded to the nearest integer
+/// with halfway cases to even.
+pub fn remainderf(x: f32, y: f32) -> f32 {
+    remquof(x, y).0
+}
+
+/// Computes the remainder like [`remainderf`](fn.remainderf.html), along with
+/// the sign and the last three bits of the quotient.
+pub fn remquof(x: f32, y: f32) -> (f32, i32) {
+    if x.is_nan() || y.is_nan() || x.is_infinite() || y == 0.0 {
+        return (f32::NAN, 0);
+    }
+
+    if y.is_infinite() {
+        return (x, 0);
+    }
+
+    let ay = fabsf(y);
+    let mut r = fabsf(x);
+    if ay <= f32::MAX / 8.0 {
+        r = fmodf(r, 8.0 * ay);
+    }
+
+    // `r` is now below `8 * ay`, so each subtraction gives one bit of the
+    // quotient and is exact.
+    let mut q = 0;
+    for &(k, bit) in &[(4.0, 4), (2.0, 2), (1.0, 1)] {
+        if r >= k * ay {
+            r -= k * ay;
+            q |= bit;
+        }
+    }
+
+    if r + r > ay || (r + r == ay && q & 1 != 0) {
+        r -= ay;
+        q += 1;
+    }
+
+    let q = q & 7;
+    let q = if x.is_sign_negative() != y.is_sign_negative() { -q } else { q };
+    (copysignf(1.0, x) * r, q)
+}
+
+/// Rounds to the nearest integer, with halfway cases to even.
+pub fn rintf(x: f32) -> f32 {
+    // Numbers from 2^23 on have no fractional part.
+    if fabsf(x) < 8_388_608.0 {
+        copysignf(AvrF32Ext::lrint(x) as f32, x)
+    } else {
+        x
+    }
+}
+
+/// Rounds to the nearest integer, with halfway cases away from zero.
+pub fn roundf(x: f32) -> f32 {
+    AvrF32Ext::round(x)
+}
+
+/// Rounds to the nearest integer, with halfway cases to even.
+pub fn roundevenf(x: f32) -> f32 {
+    rintf(x)
+}
+
+/// Multiplies a number by `2^n`.
+pub fn scalbnf(x: f32, n: i32) -> f32 {
+    ldexpf(x, n)
+}
+
+/// Computes the sine.
+pub fn sinf(x: f32) -> f32 {
+    AvrF32Ext::sin(x)
+}
+
+/// Computes the sine and the cosine.
+pub fn sincosf(x: f32) -> (f32, f32) {
+    (AvrF32Ext::sin(x), AvrF32Ext::cos(x))
+}
+
+/// Computes the hyperbolic sine.
+pub fn sinhf(x: f32) -> f32 {
+    AvrF32Ext::sinh(x)
+}
+
+/// Computes the square root.
+pub fn sqrtf(x: f32) -> f32 {
+    AvrF32Ext::sqrt(x)
+}
+
+/// Computes the tangent.
+pub fn tanf(x: f32) -> f32 {
+    AvrF32Ext::tan(x)
+}
+
+/// Computes the hyperbolic tangent.
+pub fn tanhf(x: f32) -> f32 {
+    AvrF32Ext::tanh(x)
+}
+
+/// Computes the gamma function.
+pub fn tgammaf(x: f32) -> f32 {
+    // `Γ(x) = 1 / x` to single precision, which also covers zero.
+    if fabsf(x) < 1e-8 {
+        return 1.0 / x;
+    }
+
+    if x < 0.0 {
+        let s = sin_pi(x);
+        if s == 0.0 || x == f32::NEG_INFINITY {
+            return f32::NAN;
+        }
+
+        // Reflection formula, `Γ(x) Γ(1 - x) = π / sin(π x)`.
+        return PI / (s * tgammaf(1.0 - x));
+    }
+
+    if x > 35.5 {
+        return f32::INFINITY;
+    }
+
+    // `Γ(x) = Γ(x + n) / (x (x + 1) ... (x + n - 1))`
+    let (y, product) = shift_gamma(x);
+
+    // Stirling's series, with the power split in two as `y^y` overflows
+    // before `Γ(y)` does.
+    let t = powf(y, 0.5 * (y - 0.5));
+    let gamma = t * (t * expf(-y)) * SQRT_2PI * expf(stirling_series(y));
+    gamma / product
+}
+
+/// Rounds towards zero.
+pub fn truncf(x: f32) -> f32 {
+    AvrF32Ext::trunc(x)
+}
+
+/// Computes the Bessel function of the second kind of order 0.
+pub fn y0f(x: f32) -> f32 {
+    if x < 8.0 {
+        let y = x * x;
+        let p = 228.462_28;
+        let p = -86_327.93 + y * p;
+        let p = 10_879_881.29 + y * p;
+        let p = -512_359_803.6 + y * p;
+        let p = 7_062_834_065.0 + y * p;
+        let p = -2_957_821_389.0 + y * p;
+        let q = 1.0;
+        let q = 226.103_03 + y * q;
+        let q = 47_447.266 + y * q;
+        let q = 7_189_466.5 + y * q;
+        let q = 745_249_964.8 + y * q;
+        let q = 40_076_544_269.0 + y * q;
+        p / q + FRAC_2_PI * j0f(x) * logf(x)
+    } else if x == f32::INFINITY {
+        0.0
+    } else {
+        bessel_asymptotic(x, false).1
+    }
+}
+
+/// Computes the Bessel function of the second kind of order 1.
+pub fn y1f(x: f32) -> f32 {
+    if x == 0.0 {
+        f32::NEG_INFINITY
+    } else if x < 8.0 {
+        let y = x * x;
+        let p = 8.511_937_5e3;
+        let p = -4.237_922_5e6 + y * p;
+        let p = 7.349_264_6e8 + y * p;
+        let p = -5.153_438e10 + y * p;
+        let p = 1.275_274_3e12 + y * p;
+        let p = -4.900_605e12 + y * p;
+        let q = 1.0;
+        let q = 3.549_633e2 + y * q;
+        let q = 1.020_426e5 + y * q;
+        let q = 2.245_904e7 + y * q;
+        let q = 3.733_650_4e9 + y * q;
+        let q = 4.244_419_7e11 + y * q;
+        let q = 2.499_580_6e13 + y * q;
+        x * p / q + FRAC_2_PI * (j1f(x) * logf(x) - 1.0 / x)
+    } else if x == f32::INFINITY {
+        0.0
+    } else {
+        bessel_asymptotic(x, true).1
+    }
+}
+
+/// Computes the Bessel function of the second kind of order `n`.
+pub fn ynf(n: i32, x: f32) -> f32 {
+    // `Y(-n, x) = (-1)^n Y(n, x)`
+    let negate = n < 0 && n & 1 != 0;
+    let n = n.unsigned_abs();
+    let y = match n {
+        0 => y0f(x),
+        1 => y1f(x),
+        _ => {
+            // The upward recurrence is stable for Y. It stops once it reaches
+            // negative infinity, where it would turn into NaN.
+            let (mut a, mut b) = (y0f(x), y1f(x));
+            let mut i = 1;
+            while i < n && b != f32::NEG_INFINITY {
+                let c = 2.0 * i as f32 / x * b - a;
+                a = b;
+                b = c;
+                i += 1;
+            }
+            b
+        }
+    };
+
+    if negate {
+        -y
+    } else {
+        y
+    }
+}
+
+/// The `f64` version of [`acosf`](fn.acosf.html), computed in single precision.
+pub fn acos(x: f64) -> f64 {
+    acosf(x as f32) as f64
+}
+
+/// The `f64` version of [`acoshf`](fn.acoshf.html), computed in single precision.
+pub fn acosh(x: f64) -> f64 {
+    acoshf(x as f32) as f64
+}
+
+/// The `f64` version of [`asinf`](fn.asinf.html), computed in single precision.
+pub fn asin(x: f64) -> f64 {
+    asinf(x as f32) as f64
+}
+
+/// The `f64` version of [`asinhf`](fn.asinhf.html), computed in single precision.
+pub fn asinh(x: f64) -> f64 {
+    asinhf(x as f32) as f64
+}
+
+/// The `f64` version of [`atanf`](fn.atanf.html), computed in single precision.
+pub fn atan(x: f64) -> f64 {
+    atanf(x as f32) as f64
+}
+
+/// The `f64` version of [`atan2f`](fn.atan2f.html), computed in single precision.
+pub fn atan2(y: f64, x: f64) -> f64 {
+    atan2f(y as f32, x as f32) as f64
+}
+
+/// The `f64` version of [`atanhf`](fn.atanhf.html), computed in single precision.
+pub fn atanh(x: f64) -> f64 {
+    atanhf(x as f32) as f64
+}
+
+/// The `f64` version of [`cbrtf`](fn.cbrtf.html), computed in single precision.
+pub fn cbrt(x: f64) -> f64 {
+    cbrtf(x as f32) as f64
+}
+
+/// Rounds towards positive infinity.
+pub fn ceil(x: f64) -> f64 {
+    let t = trunc(x);
+    if t < x {
+        t + 1.0
+    } else {
+        t
+    }
+}
+
+/// Gets `x` with the sign of `y`.
+pub fn copysign(x: f64, y: f64) -> f64 {
+    f64::from_bits((x.to_bits() & !(1 << 63)) | (y.to_bits() & (1 << 63)))
+}
+
+/// The `f64` version of [`cosf`](fn.cosf.html), computed in single precision.
+pub fn cos(x: f64) -> f64 {
+    cosf(x as f32) as f64
+}
+
+/// The `f64` version of [`coshf`](fn.coshf.html), computed in single precision.
+pub fn cosh(x: f64) -> f64 {
+    coshf(x as f32) as f64
+}
+
+/// The `f64` version of [`erff`](fn.erff.html), computed in single precision.
+pub fn erf(x: f64) -> f64 {
+    erff(x as f32) as f64
+}
+
+/// The `f64` version of [`erfcf`](fn.erfcf.html), computed in single precision.
+pub fn erfc(x: f64) -> f64 {
+    erfcf(x as f32) as f64
+}
+
+/// The `f64` version of [`expf`](fn.expf.html), computed in single precision.
+pub fn exp(x: f64) -> f64 {
+    expf(x as f32) as f64
+}
+
+/// The `f64` version of [`exp2f`](fn.exp2f.html), computed in single precision.
+pub fn exp2(x: f64) -> f64 {
+    exp2f(x as f32) as f64
+}
+
+/// The `f64` version of [`exp10f`](fn.exp10f.html), computed in single precision.
+pub fn exp10(x: f64) -> f64 {
+    exp10f(x as f32) as f64
+}
+
+/// The `f64` version of [`expm1f`](fn.expm1f.html), computed in single precision.
+pub fn expm1(x: f64) -> f64 {
+    expm1f(x as f32) as f64
+}
+
+/// Gets the absolute value.
+pub fn fabs(x: f64) -> f64 {
+    f64::from_bits(x.to_bits() & !(1 << 63))
+}
+
+/// Computes `x - y` if it is positive, and zero otherwise.
+pub fn fdim(x: f64, y: f64) -> f64 {
+    if x <= y {
+        0.0
+    } else {
+        x - y
+    }
+}
+
+/// Rounds towards negative infinity.
+pub fn floor(x: f64) -> f64 {
+    let t = trunc(x);
+    if t > x {
+        t - 1.0
+    } else {
+        t
+    }
+}
+
+/// The `f64` version of [`fmaf`](fn.fmaf.html), computed in single precision.
+pub fn fma(x: f64, y: f64, z: f64) -> f64 {
+    fmaf(x as f32, y as f32, z as f32) as f64
+}
+
+/// Gets the larger number, ignoring NaN.
+pub fn fmax(x: f64, y: f64) -> f64 {
+    if x.is_nan() || x < y {
+        y
+    } else {
+        x
+    }
+}
+
+/// Gets the larger number, propagating NaN and ordering `-0.0` below `0.0`.
+pub fn fmaximum(x: f64, y: f64) -> f64 {
+    if x.is_nan() {
+        x
+    } else if y.is_nan() {
+        y
+    } else if x > y || (y.to_bits() == (-0.0f64).to_bits() && x.is_sign_positive()) {
+        x
+    } else {
+        y
+    }
+}
+
+/// Gets the larger number, ignoring NaN and ordering `-0.0` below `0.0`.
+pub fn fmaximum_num(x: f64, y: f64) -> f64 {
+    if x > y || y.is_nan() {
+        x
+    } else if y > x || x.is_nan() || x.is_sign_negative() {
+        y
+    } else {
+        x
+    }
+}
+
+/// Gets the smaller number, ignoring NaN.
+pub fn fmin(x: f64, y: f64) -> f64 {
+    if y.is_nan() || x < y {
+        x
+    } else {
+        y
+    }
+}
+
+/// Gets the smaller number, propagating NaN and ordering `-0.0` below `0.0`.
+pub fn fminimum(x: f64, y: f64) -> f64 {
+    if x.is_nan() {
+        x
+    } else if y.is_nan() {
+        y
+    } else if x < y || (x.to_bits() == (-0.0f64).to_bits() && y.is_sign_positive()) {
+        x
+    } else {
+        y
+    }
+}
+
+/// Gets the smaller number, ignoring NaN and ordering `-0.0` below `0.0`.
+pub fn fminimum_num(x: f64, y: f64) -> f64 {
+    if x > y || x.is_nan() {
+        y
+    } else if y > x || y.is_nan() || x.is_sign_negative() {
+        x
+    } else {
+        y
+    }
+}
+
+/// The `f64` version of [`fmodf`](fn.fmodf.html), computed in single precision.
+pub fn fmod(x: f64, y: f64) -> f64 {
+    fmodf(x as f32, y as f32) as f64
+}
+
+/// Splits a number into a mantissa from 0.5 to 1 and a power of two.
+pub fn frexp(x: f64) -> (f64, i32) {
+    let bits = x.to_bits();
+    let exp = ((bits >> 52) & 0x7ff) as i32;
+
+    if exp == 0 {
+        if x == 0.0 {
+            return (x, 0);
+        }
+
+        // Subnormal numbers are scaled by 2^64 first.
+        let (mantissa, exp) = frexp(x * f64::from_bits(0x43f0_0000_0000_0000));
+        (mantissa, exp - 64)
+    } else if exp == 0x7ff {
+        (x, 0)
+    } else {
+        (f64::from_bits((bits & 0x800f_ffff_ffff_ffff) | 0x3fe0_0000_0000_0000), exp - 1022)
+    }
+}
+
+/// The `f64` version of [`hypotf`](fn.hypotf.html), computed in single precision.
+pub fn hypot(x: f64, y: f64) -> f64 {
+    hypotf(x as f32, y as f32) as f64
+}
+
+/// Gets the exponent of a number, `i32::MIN` for zero and NaN and
+/// `i32::MAX` for infinity.
+pub fn ilogb(x: f64) -> i32 {
+    let bits = x.to_bits();
+    let exp = ((bits >> 52) & 0x7ff) as i32;
+    let mantissa = bits << 12;
+
+    match exp {
+        0 if mantissa == 0 => i32::MIN,
+        0 => -1023 - mantissa.leading_zeros() as i32,
+        0x7ff if mantissa == 0 => i32::MAX,
+        0x7ff => i32::MIN,
+        _ => exp - 1023,
+    }
+}
+
+/// The `f64` version of [`j0f`](fn.j0f.html), computed in single precision.
+pub fn j0(x: f64) -> f64 {
+    j0f(x as f32) as f64
+}
+
+/// The `f64` version of [`j1f`](fn.j1f.html), computed in single precision.
+pub fn j1(x: f64) -> f64 {
+    j1f(x as f32) as f64
+}
+
+/// The `f64` version of [`jnf`](fn.jnf.html), computed in single precision.
+pub fn jn(n: i32, x: f64) -> f64 {
+    jnf(n, x as f32) as f64
+}
+
+/// Multiplies a number by `2^n`.
+pub fn ldexp(x: f64, n: i32) -> f64 {
+    scalbn(x, n)
+}
+
+/// The `f64` version of [`lgammaf`](fn.lgammaf.html), computed in single precision.
+pub fn lgamma(x: f64) -> f64 {
+    lgammaf(x as f32) as f64
+}
+
+/// The `f64` version of [`lgammaf_r`](fn.lgammaf_r.html), computed in single precision.
+pub fn lgamma_r(x: f64) -> (f64, i32) {
+    let (y, sign) = lgammaf_r(x as f32);
+    (y as f64, sign)
+}
+
+/// The `f64` version of [`logf`](fn.logf.html), computed in single precision.
+pub fn log(x: f64) -> f64 {
+    logf(x as f32) as f64
+}
+
+/// The `f64` version of [`log10f`](fn.log10f.html), computed in single precision.
+pub fn log10(x: f64) -> f64 {
+    log10f(x as f32) as f64
+}
+
+/// The `f64` version of [`log1pf`](fn.log1pf.html), computed in single precision.
+pub fn log1p(x: f64) -> f64 {
+    log1pf(x as f32) as f64
+}
+
+/// The `f64` version of [`log2f`](fn.log2f.html), computed in single precision.
+pub fn log2(x: f64) -> f64 {
+    log2f(x as f32) as f64
+}
+
+/// Splits a number into its fractional and integral parts.
+pub fn modf(x: f64) -> (f64, f64) {
+    let t = trunc(x);
+    if x.is_infinite() {
+        (copysign(0.0, x), x)
+    } else {
+        (copysign(x - t, x), t)
+    }
+}
+
+/// Gets the next representable number after `x` in the direction of `y`.
+pub fn nextafter(x: f64, y: f64) -> f64 {
+    if x.is_nan() || y.is_nan() {
+        return x + y;
+    }
+
+    let (ux, uy) = (x.to_bits(), y.to_bits());
+    if ux == uy {
+        return y;
+    }
+
+    let (ax, ay) = (ux & !(1 << 63), uy & !(1 << 63));
+    let next = if ax == 0 {
+        if ay == 0 {
+            return y;
+        }
+        (uy & (1 << 63)) | 1
+    } else if ax > ay || (ux ^ uy) & (1 << 63) != 0 {
+        ux - 1
+    } else {
+        ux + 1
+    };
+
+    f64::from_bits(next)
+}
+
+/// The `f64` version of [`powf`](fn.powf.html), computed in single precision.
+pub fn pow(x: f64, y: f64) -> f64 {
+    powf(x as f32, y as f32) as f64
+}
+
+/// The `f64` version of [`remainderf`](fn.remainderf.html), computed in single precision.
+pub fn remainder(x: f64, y: f64) -> f64 {
+    remainderf(x as f32, y as f32) as f64
+}
+
+/// The `f64` version of [`remquof`](fn.remquof.html), computed in single precision.
+pub fn remquo(x: f64, y: f64) -> (f64, i32) {
+    let (r, q) = remquof(x as f32, y as f32);
+    (r as f64, q)
+}
+
+/// Rounds to the nearest integer, with halfway cases to even.
+pub fn rint(x: f64) -> f64 {
+    // Adding 2^52 leaves no room for a fractional part, so the addition
+    // rounds to an integer. Numbers from 2^52 on have none to begin with.
+    const TO_INT: f64 = 4_503_599_627_370_496.0;
+
+    if fabs(x) < TO_INT {
+        let y = if x < 0.0 { x - TO_INT + TO_INT } else { x + TO_INT - TO_INT };
+        copysign(y, x)
+    } else {
+        x
+    }
+}
+
+/// Rounds to the nearest integer, with halfway cases away from zero.
+pub fn round(x: f64) -> f64 {
+    let t = trunc(x);
+    if fabs(x - t) >= 0.5 {
+        t + copysign(1.0, x)
+    } else {
+        t
+    }
+}
+
+/// Rounds to the nearest integer, with halfway cases to even.
+pub fn roundeven(x: f64) -> f64 {
+    rint(x)
+}
+
+/// Multiplies a number by `2^n`.
+pub fn scalbn(x: f64, n: i32) -> f64 {
+    let max = f64::from_bits(0x7fe0_0000_0000_0000);
+    // 2^-1022 * 2^53, which keeps subnormal results rounded only once.
+    let min = f64::from_bits(0x0010_0000_0000_0000) * f64::from_bits(0x4340_0000_0000_0000);
+
+    let (mut y, mut n) = (x, n);
+    if n > 1023 {
+        y *= max;
+        n -= 1023;
+        if n > 1023 {
+            y *= max;
+            n = (n - 1023).min(1023);
+        }
+    } else if n < -1022 {
+        y *= min;
+        n += 1022 - 53;
+        if n < -1022 {
+            y *= min;
+            n = (n + 1022 - 53).max(-1022);
+        }
+    }
+
+    y * f64::from_bits(((0x3ff + n) as u64) << 52)
+}
+
+/// The `f64` version of [`sinf`](fn.sinf.html), computed in single precision.
+pub fn sin(x: f64) -> f64 {
+    sinf(x as f32) as f64
+}
+
+/// The `f64` version of [`sincosf`](fn.sincosf.html), computed in single precision.
+pub fn sincos(x: f64) -> (f64, f64) {
+    let (s, c) = sincosf(x as f32);
+    (s as f64, c as f64)
+}
+
+/// The `f64` version of [`sinhf`](fn.sinhf.html), computed in single precision.
+pub fn sinh(x: f64) -> f64 {
+    sinhf(x as f32) as f64
+}
+
+/// The `f64` version of [`sqrtf`](fn.sqrtf.html), computed in single precision.
+pub fn sqrt(x: f64) -> f64 {
+    sqrtf(x as f32) as f64
+}
+
+/// The `f64` version of [`tanf`](fn.tanf.html), computed in single precision.
+pub fn tan(x: f64) -> f64 {
+    tanf(x as f32) as f64
+}
+
+/// The `f64` version of [`tanhf`](fn.tanhf.html), computed in single precision.
+pub fn tanh(x: f64) -> f64 {
+    tanhf(x as f32) as f64
+}
+
+/// The `f64` version of [`tgammaf`](fn.tgammaf.html), computed in single precision.
+pub fn tgamma(x: f64) -> f64 {
+    tgammaf(x as f32) as f64
+}
+
+/// Rounds towards zero.
+pub fn trunc(x: f64) -> f64 {
+    let bits = x.to_bits();
+    let exp = ((bits >> 52) & 0x7ff) as i32 - 1023;
+
+    if exp >= 52 {
+        // Also infinity and NaN.
+        x
+    } else if exp < 0 {
+        copysign(0.0, x)
+    } else {
+        f64::from_bits(bits & !((1 << (52 - exp)) - 1))
+    }
+}
+
+/// The `f64` version of [`y0f`](fn.y0f.html), computed in single precision.
+pub fn y0(x: f64) -> f64 {
+    y0f(x as f32) as f64
+}
+
+/// The `f64` version of [`y1f`](fn.y1f.html), computed in single precision.
+pub fn y1(x: f64) -> f64 {
+    y1f(x as f32) as f64
+}
+
+/// The `f64` version of [`ynf`](fn.ynf.html), computed in single precision.
+pub fn yn(n: i32, x: f64) -> f64 {
+    ynf(n, x as f32) as f64
+}
+
+/// `sqrt(2π)`
+const SQRT_2PI: f32 = 2.506_628_3;
+/// `ln(sqrt(2π))`
+const LN_SQRT_2PI: f32 = 0.918_938_5;
+
+/// Computes `sin(π x)`, which is exactly zero for integers.
+fn sin_pi(x: f32) -> f32 {
+    // The argument is reduced to [0, 1/2] first, which is exact, so that
+    // the result stays precise near integers.
+    let mut r = fabsf(x);
+    r -= 2.0 * floorf(0.5 * r);
+
+    let mut sign = copysignf(1.0, x);
+    if r >= 1.0 {
+        r -= 1.0;
+        sign = -sign;
+    }
+    if r > 0.5 {
+        r = 1.0 - r;
+    }
+
+    sign * sinf(PI * r)
+}
+
+/// Shifts `x > 0` up to at least 8, where Stirling's series is accurate,
+/// returning the shifted value and the product of the steps.
+fn shift_gamma(x: f32) -> (f32, f32) {
+    let (mut y, mut product) = (x, 1.0);
+    while y < 8.0 {
+        product *= y;
+        y += 1.0;
+    }
+    (y, product)
+}
+
+/// Computes the correction terms of Stirling's series for `ln(Γ(y))`.
+fn stirling_series(y: f32) -> f32 {
+    let r = 1.0 / y;
+    let r2 = r * r;
+    r * (1.0 / 12.0 - r2 * (1.0 / 360.0 - r2 * (1.0 / 1260.0)))
+}
+
+/// Computes `ln(Γ(x))` for `x > 0`.
+fn ln_gamma(x: f32) -> f32 {
+    let (y, product) = shift_gamma(x);
+    (y - 0.5) * logf(y) - y + LN_SQRT_2PI + stirling_series(y) - logf(product)
+}
+
+/// Computes `J(n, x)` for `n >= 2` and a finite `x > 0`.
+fn bessel_jn(n: u32, x: f32) -> f32 {
+    if x < 1e-4 {
+        // The leading term of the series, `(x / 2)^n / n!`, as the
+        // recurrences below would overflow.
+        let mut y = 1.0;
+        for i in 1..=n {
+            y *= 0.5 * x / i as f32;
+        }
+        return y;
+    }
+
+    let tox = 2.0 / x;
+
+    if x > n as f32 {
+        // The upward recurrence is stable above the order.
+        let (mut a, mut b) = (j0f(x), j1f(x));
+        for i in 1..n {
+            let c = i as f32 * tox * b - a;
+            a = b;
+            b = c;
+        }
+        return b;
+    }
+
+    // Miller's algorithm: the downward recurrence from an order high enough
+    // for its terms to be negligible, normalized by `1 = J0 + 2 (J2 + J4 + ...)`.
+    let m = 2 * ((n + AvrF32Ext::sqrt(40.0 * n as f32) as u32) / 2);
+    let (mut next, mut current) = (0.0, 1.0);
+    let (mut sum, mut y) = (0.0, 0.0);
+
+    for j in (1..=m).rev() {
+        let previous = j as f32 * tox * current - next;
+        next = current;
+        current = previous;
+
+        // Rescales everything before it overflows.
+        if fabsf(current) > 1e10 {
+            current *= 1e-10;
+            next *= 1e-10;
+            sum *= 1e-10;
+            y *= 1e-10;
+        }
+
+        if j & 1 != 0 {
+            sum += current;
+        }
+        if j == n {
+            y = next;
+        }
+    }
+
+    y / (2.0 * sum - current)
+}
+
+/// Computes the asymptotic forms of `(J0(x), Y0(x))`, or `(J1(x), Y1(x))`
+/// with `one`, for `x >= 8`.
+fn bessel_asymptotic(x: f32, one: bool) -> (f32, f32) {
+    let z = 8.0 / x;
+    let y = z * z;
+
+    let (p, q) = if one {
+        let p = -2.403_370_2e-7;
+        let p = 2.457_520_2e-6 + y * p;
+        let p = -3.516_396_5e-5 + y * p;
+        let p = 0.183_105e-2 + y * p;
+        let p = 1.0 + y * p;
+        let q = 0.105_787_412e-6;
+        let q = -8.822_899e-7 + y * q;
+        let q = 8.449_199e-6 + y * q;
+        let q = -2.002_690_9e-4 + y * q;
+        let q = 0.046_875 + y * q;
+        (p, q)
+    } else {
+        let p = 2.093_887_2e-7;
+        let p = -2.073_370_8e-6 + y * p;
+        let p = 2.734_510_4e-5 + y * p;
+        let p = -1.098_628_6e-3 + y * p;
+        let p = 1.0 + y * p;
+        let q = -9.349_451e-8;
+        let q = 7.621_095e-7 + y * q;
+        let q = -6.911_147_5e-6 + y * q;
+        let q = 1.430_488_8e-4 + y * q;
+        let q = -1.562_5e-2 + y * q;
+        (p, q)
+    };
+
+    // The sine and cosine of `x - 3π/4`, or `x - π/4`, from those of `x` as
+    // the subtraction would lose the phase for large numbers.
+    let (s, c) = sincosf(x);
+    let (s, c) = if one {
+        (-(s + c) * FRAC_1_SQRT_2, (s - c) * FRAC_1_SQRT_2)
+    } else {
+        ((s - c) * FRAC_1_SQRT_2, (s + c) * FRAC_1_SQRT_2)
+    };
+    let amplitude = AvrF32Ext::sqrt(FRAC_2_PI / x);
+    (amplitude * (c * p - z * s * q), amplitude * (s * p + z * c * q))
+}
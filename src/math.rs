@@ -0,0 +1,221 @@
+//! Floating point math from `math.h`.
+//!
+//! avr-libc implements its math functions in hand-written assembly on 32 bit
+//! floats, which is both smaller and faster than the generic software versions.
+//! [`AvrF32Ext`](trait.AvrF32Ext.html) makes them available as methods on `f32`:
+//!
+//! ```ignore
+//! use avr_libc::math::AvrF32Ext;
+//!
+//! let heading = AvrF32Ext::atan2(y, x).to_degrees();
+//! let distance = AvrF32Ext::hypot(x, y);
+//! ```
+
+use bindings::{sin, cos, tan, asin, acos, atan, atan2, sinh, cosh, tanh};
+use bindings::{sqrt, cbrt, square, hypot, exp, log, log10, pow, fma, fmod, fdim};
+use bindings::{floor, ceil, trunc, round, lround, lrint, modf, frexp, ldexp};
+use rust_ctypes::c_int;
+
+/// Math functions on `f32`, implemented by avr-libc.
+///
+/// The methods are named like their counterparts on `f32` in `std`. Functions
+/// which `core` already provides, such as `abs` or `min`, are not repeated.
+///
+/// Inherent methods take precedence over trait methods, so `x.sqrt()` calls the
+/// method of `f32` instead wherever one exists, such as with `std` or once
+/// `core` gains it. Call the methods through the trait, as in
+/// `AvrF32Ext::sqrt(x)`, to be sure to get avr-libc's version.
+pub trait AvrF32Ext: Sized {
+    /// Computes the sine, in radians.
+    fn sin(self) -> Self;
+    /// Computes the cosine, in radians.
+    fn cos(self) -> Self;
+    /// Computes the tangent, in radians.
+    fn tan(self) -> Self;
+    /// Computes the arcsine, in radians from -π/2 to π/2.
+    fn asin(self) -> Self;
+    /// Computes the arccosine, in radians from 0 to π.
+    fn acos(self) -> Self;
+    /// Computes the arctangent, in radians from -π/2 to π/2.
+    fn atan(self) -> Self;
+    /// Computes the four quadrant arctangent of `self` (y) and `x`, in radians from -π to π.
+    fn atan2(self, x: Self) -> Self;
+    /// Computes the hyperbolic sine.
+    fn sinh(self) -> Self;
+    /// Computes the hyperbolic cosine.
+    fn cosh(self) -> Self;
+    /// Computes the hyperbolic tangent.
+    fn tanh(self) -> Self;
+
+    /// Computes the square root, or NaN for negative numbers.
+    fn sqrt(self) -> Self;
+    /// Computes the cube root.
+    fn cbrt(self) -> Self;
+    /// Computes `self * self`.
+    fn square(self) -> Self;
+    /// Computes the length of the hypotenuse of a right triangle with legs `self` and `other`.
+    ///
+    /// Unlike the naive formula, this does not overflow for large values.
+    fn hypot(self, other: Self) -> Self;
+    /// Computes `e^self`.
+    fn exp(self) -> Self;
+    /// Computes the natural logarithm.
+    fn ln(self) -> Self;
+    /// Computes the base 10 logarithm.
+    fn log10(self) -> Self;
+    /// Raises `self` to a floating point power.
+    fn powf(self, n: Self) -> Self;
+    /// Computes `self * a + b` with a single rounding.
+    fn mul_add(self, a: Self, b: Self) -> Self;
+    /// Computes the remainder of `self / other`, with the sign of `self`.
+    fn fmod(self, other: Self) -> Self;
+    /// Computes `self - other` if it is positive, and zero otherwise.
+    fn positive_diff(self, other: Self) -> Self;
+
+    /// Rounds towards negative infinity.
+    fn floor(self) -> Self;
+    /// Rounds towards positive infinity.
+    fn ceil(self) -> Self;
+    /// Rounds towards zero.
+    fn trunc(self) -> Self;
+    /// Rounds to the nearest integer, with halfway cases away from zero.
+    fn round(self) -> Self;
+    /// Rounds to the nearest integer like [`round`](#tymethod.round), returning
+    /// `i32::MIN` if the result does not fit.
+    fn lround(self) -> i32;
+    /// Rounds to the nearest integer, with halfway cases to even, returning
+    /// `i32::MIN` if the result does not fit.
+    fn lrint(self) -> i32;
+    /// Splits the number into its fractional and integral parts, both with the sign of `self`.
+    fn modf(self) -> (Self, Self);
+    /// Splits the number into a mantissa from 0.5 to 1 and a power of two.
+    fn frexp(self) -> (Self, i16);
+    /// Multiplies the number by `2^exp`.
+    fn ldexp(self, exp: i16) -> Self;
+}
+
+impl AvrF32Ext for f32 {
+    fn sin(self) -> f32 {
+        unsafe { sin(self) }
+    }
+
+    fn cos(self) -> f32 {
+        unsafe { cos(self) }
+    }
+
+    fn tan(self) -> f32 {
+        unsafe { tan(self) }
+    }
+
+    fn asin(self) -> f32 {
+        unsafe { asin(self) }
+    }
+
+    fn acos(self) -> f32 {
+        unsafe { acos(self) }
+    }
+
+    fn atan(self) -> f32 {
+        unsafe { atan(self) }
+    }
+
+    fn atan2(self, x: f32) -> f32 {
+        unsafe { atan2(self, x) }
+    }
+
+    fn sinh(self) -> f32 {
+        unsafe { sinh(self) }
+    }
+
+    fn cosh(self) -> f32 {
+        unsafe { cosh(self) }
+    }
+
+    fn tanh(self) -> f32 {
+        unsafe { tanh(self) }
+    }
+
+    fn sqrt(self) -> f32 {
+        unsafe { sqrt(self) }
+    }
+
+    fn cbrt(self) -> f32 {
+        unsafe { cbrt(self) }
+    }
+
+    fn square(self) -> f32 {
+        unsafe { square(self) }
+    }
+
+    fn hypot(self, other: f32) -> f32 {
+        unsafe { hypot(self, other) }
+    }
+
+    fn exp(self) -> f32 {
+        unsafe { exp(self) }
+    }
+
+    fn ln(self) -> f32 {
+        unsafe { log(self) }
+    }
+
+    fn log10(self) -> f32 {
+        unsafe { log10(self) }
+    }
+
+    fn powf(self, n: f32) -> f32 {
+        unsafe { pow(self, n) }
+    }
+
+    fn mul_add(self, a: f32, b: f32) -> f32 {
+        unsafe { fma(self, a, b) }
+    }
+
+    fn fmod(self, other: f32) -> f32 {
+        unsafe { fmod(self, other) }
+    }
+
+    fn positive_diff(self, other: f32) -> f32 {
+        unsafe { fdim(self, other) }
+    }
+
+    fn floor(self) -> f32 {
+        unsafe { floor(self) }
+    }
+
+    fn ceil(self) -> f32 {
+        unsafe { ceil(self) }
+    }
+
+    fn trunc(self) -> f32 {
+        unsafe { trunc(self) }
+    }
+
+    fn round(self) -> f32 {
+        unsafe { round(self) }
+    }
+
+    fn lround(self) -> i32 {
+        unsafe { lround(self) }
+    }
+
+    fn lrint(self) -> i32 {
+        unsafe { lrint(self) }
+    }
+
+    fn modf(self) -> (f32, f32) {
+        let mut integral = 0.0;
+        let fractional = unsafe { modf(self, &mut integral) };
+        (fractional, integral)
+    }
+
+    fn frexp(self) -> (f32, i16) {
+        let mut exp: c_int = 0;
+        let mantissa = unsafe { frexp(self, &mut exp) };
+        (mantissa, exp)
+    }
+
+    fn ldexp(self, exp: i16) -> f32 {
+        unsafe { ldexp(self, exp) }
+    }
+}
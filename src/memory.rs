@@ -0,0 +1,221 @@
+//! Heap and stack usage introspection.
+//!
+//! On AVR the heap grows up from the end of `.bss` and the stack grows down
+//! from the end of RAM, with nothing stopping one from running into the other.
+//! The functions in this module report how close they are, using the state
+//! kept by avr-libc's `malloc`.
+//!
+//! Peak usage can be measured by painting the unused memory between the heap
+//! and the stack with a known pattern and later checking how much of it has
+//! been overwritten:
+//!
+//! ```ignore
+//! memory::paint();
+//! run_application();
+//! let marks = memory::watermarks().unwrap();
+//! ```
+
+use bindings::{size_t, __malloc_heap_start, __malloc_heap_end};
+
+use core::{cmp, ptr};
+
+/// A block on the free list kept by `malloc`.
+#[repr(C)]
+struct FreeBlock {
+    /// The usable size of the block, not including this field.
+    size: size_t,
+    next: *mut FreeBlock,
+}
+
+extern "C" {
+    /// The end of the memory handed out by `malloc` so far, null before the first allocation.
+    pub(crate) static mut __brkval: *mut u8;
+    /// The head of the free list.
+    static mut __flp: *mut FreeBlock;
+    /// The end of `.bss`, set by the linker.
+    static __heap_start: u8;
+    /// The initial stack pointer, set by the linker to the end of RAM.
+    static __stack: u8;
+}
+
+/// The byte unused memory is painted with.
+pub const CANARY: u8 = 0xc5;
+
+/// The number of bytes below the stack pointer which are left unpainted, for
+/// the return addresses and saved registers of the painting code itself.
+const PAINT_GUARD: usize = 32;
+
+/// The range painted by the last call to `paint`.
+static mut PAINTED: Option<(usize, usize)> = None;
+
+/// Gets the approximate current stack pointer.
+#[inline(never)]
+pub fn stack_pointer() -> usize {
+    let marker = 0u8;
+    &marker as *const u8 as usize
+}
+
+/// Gets the address of the top of the stack, where it starts growing down from.
+pub fn stack_top() -> usize {
+    unsafe { &__stack as *const u8 as usize }
+}
+
+/// Gets the number of bytes currently used by the stack.
+pub fn stack_used() -> usize {
+    (stack_top() + 1).saturating_sub(stack_pointer())
+}
+
+/// Gets the address the heap starts at.
+pub fn heap_start() -> usize {
+    unsafe { *ptr::addr_of!(__malloc_heap_start) as usize }
+}
+
+/// Gets the first address above the memory handed out by `malloc`.
+///
+/// This only moves down again when the topmost block is freed, so it is the
+/// high-water mark of the heap as long as blocks are freed in any other order.
+pub fn heap_end() -> usize {
+    let brk = unsafe { *ptr::addr_of!(__brkval) };
+    if brk.is_null() {
+        heap_start()
+    } else {
+        brk as usize
+    }
+}
+
+/// Gets the number of bytes handed out by `malloc`, including free blocks below the heap end.
+pub fn heap_used() -> usize {
+    heap_end() - heap_start()
+}
+
+/// Checks if the heap grows towards the stack, which is the case unless its end has been set.
+pub fn heap_grows_into_stack() -> bool {
+    unsafe { (*ptr::addr_of!(__malloc_heap_end)).is_null() }
+}
+
+/// Gets the number of bytes between the end of the heap and the stack pointer.
+///
+/// This is the amount of memory left for the stack, and for the heap if it grows
+/// towards the stack. Free blocks on the heap are not included.
+pub fn free_memory() -> usize {
+    stack_pointer().saturating_sub(gap_start())
+}
+
+/// Gets the start of the unused memory below the stack.
+fn gap_start() -> usize {
+    let bss_end = unsafe { &__heap_start as *const u8 as usize };
+
+    if heap_grows_into_stack() {
+        cmp::max(heap_end(), bss_end)
+    } else {
+        bss_end
+    }
+}
+
+/// Statistics about the blocks on `malloc`'s free list.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FreeList {
+    /// The number of free blocks.
+    pub blocks: usize,
+    /// The total usable size of the free blocks.
+    pub bytes: usize,
+    /// The usable size of the largest free block.
+    pub largest: usize,
+}
+
+impl FreeList {
+    /// Gets how fragmented the free memory is, from 0 to 100 percent.
+    ///
+    /// This is the share of free bytes which are not part of the largest free
+    /// block, and is 0 when the free list is empty or holds a single block.
+    pub fn fragmentation(&self) -> u8 {
+        if self.bytes == 0 {
+            0
+        } else {
+            (100 - self.largest as u32 * 100 / self.bytes as u32) as u8
+        }
+    }
+}
+
+/// Walks `malloc`'s free list.
+///
+/// Must not be called while an interrupt handler might be allocating.
+pub fn free_list() -> FreeList {
+    let mut stats = FreeList::default();
+
+    unsafe {
+        let mut block = *ptr::addr_of!(__flp);
+        while !block.is_null() {
+            let size = (*block).size as usize;
+
+            stats.blocks += 1;
+            stats.bytes += size;
+            if size > stats.largest {
+                stats.largest = size;
+            }
+            block = (*block).next;
+        }
+    }
+    stats
+}
+
+/// Fills the free memory between the heap and the stack with [`CANARY`](constant.CANARY.html).
+///
+/// [`watermarks`](fn.watermarks.html) can afterwards tell how much of it has
+/// been used. The few bytes right below the stack pointer are left untouched.
+pub fn paint() {
+    let start = gap_start();
+    let end = stack_pointer().saturating_sub(PAINT_GUARD);
+
+    if start >= end {
+        return;
+    }
+
+    unsafe {
+        let mut address = start;
+        while address < end {
+            ptr::write_volatile(address as *mut u8, CANARY);
+            address += 1;
+        }
+        *ptr::addr_of_mut!(PAINTED) = Some((start, end));
+    }
+}
+
+/// Peak memory usage measured since the last call to [`paint`](fn.paint.html).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Watermarks {
+    /// The highest number of bytes used by the stack.
+    pub stack_peak: usize,
+    /// The highest number of bytes used by the heap, if it grows towards the stack.
+    pub heap_peak: Option<usize>,
+    /// The smallest number of bytes that were left between the heap and the stack.
+    pub min_free: usize,
+}
+
+/// Measures peak memory usage by checking how much of the painted memory has been overwritten.
+///
+/// Returns `None` if [`paint`](fn.paint.html) has not been called. The results
+/// are approximate: allocated memory which has not been written to yet counts
+/// as free, and data which happens to equal the canary byte goes unnoticed.
+pub fn watermarks() -> Option<Watermarks> {
+    let (start, end) = unsafe { (*ptr::addr_of!(PAINTED))? };
+
+    unsafe {
+        // The heap overwrites the painted memory from below, the stack from above.
+        let mut low = start;
+        while low < end && ptr::read_volatile(low as *const u8) != CANARY {
+            low += 1;
+        }
+
+        let mut high = end;
+        while high > low && ptr::read_volatile((high - 1) as *const u8) != CANARY {
+            high -= 1;
+        }
+
+        Some(Watermarks {
+            stack_peak: stack_top() + 1 - high,
+            heap_peak: if heap_grows_into_stack() { Some(low.saturating_sub(heap_start())) } else { None },
+            min_free: high - low,
+        })
+    }
+}
@@ -0,0 +1,246 @@
+//! Number to string conversion with `itoa`, `dtostrf` and friends.
+//!
+//! These functions are far smaller than `core::fmt`. The number is written into
+//! a byte array on the stack, which is checked at compile time to be large enough
+//! for any value. The `*_len` functions give the size to use:
+//!
+//! ```ignore
+//! let mut buf = [0; numfmt::int_len::<u16>(16)];
+//! let hex = numfmt::format_int_radix::<_, 16, _>(address, &mut buf);
+//!
+//! let mut buf = [0; numfmt::f32_len(0, 2)];
+//! let celsius = numfmt::format_f32::<0, 2, _>(temperature, &mut buf);
+//! ```
+//!
+//! Using a buffer which is too small fails to compile.
+
+use bindings::{dtostre, dtostrf, strlen};
+use bindings::{DTOSTR_ALWAYS_SIGN, DTOSTR_PLUS_SIGN, DTOSTR_UPPERCASE};
+use rust_ctypes::{c_char, c_int, c_long, c_uchar, c_uint, c_ulong};
+
+use core::marker::PhantomData;
+use core::ops::{BitOr, BitOrAssign};
+use core::str;
+
+extern "C" {
+    // Variants without the check of the radix, which is done at compile time here.
+    fn __itoa_ncheck(val: c_int, s: *mut c_char, radix: c_uchar) -> *mut c_char;
+    fn __utoa_ncheck(val: c_uint, s: *mut c_char, radix: c_uchar) -> *mut c_char;
+    fn __ltoa_ncheck(val: c_long, s: *mut c_char, radix: c_uchar) -> *mut c_char;
+    fn __ultoa_ncheck(val: c_ulong, s: *mut c_char, radix: c_uchar) -> *mut c_char;
+}
+
+/// An integer type which can be converted by the `itoa` family of functions.
+///
+/// Negative numbers only get a minus sign in base 10. In other bases, their
+/// two's complement is written out.
+///
+/// # Safety
+///
+/// `write` must not write more than [`int_len`](fn.int_len.html) bytes for `BITS` and `SIGNED`.
+pub unsafe trait Integer: Copy {
+    /// The number of bits of the type.
+    const BITS: u32;
+    /// Whether the type is signed.
+    const SIGNED: bool;
+
+    #[doc(hidden)]
+    unsafe fn write(self, s: *mut c_char, radix: u8);
+}
+
+macro_rules! impl_integer {
+    ($($ty:ident => $f:ident, $signed:expr;)*) => {
+        $(
+            unsafe impl Integer for $ty {
+                const BITS: u32 = $ty::BITS;
+                const SIGNED: bool = $signed;
+
+                unsafe fn write(self, s: *mut c_char, radix: u8) {
+                    $f(self, s, radix);
+                }
+            }
+        )*
+    };
+}
+
+impl_integer! {
+    i16 => __itoa_ncheck, true;
+    u16 => __utoa_ncheck, false;
+    i32 => __ltoa_ncheck, true;
+    u32 => __ultoa_ncheck, false;
+}
+
+/// Gets the buffer size needed to convert any `T` in base `radix`, including the terminator.
+pub const fn int_len<T: Integer>(radix: u8) -> usize {
+    assert!(radix >= 2 && radix <= 36, "radix must be between 2 and 36");
+
+    // The largest magnitude, which for negative numbers is the most negative one.
+    let negative = T::SIGNED && radix == 10;
+    let mut magnitude = if negative {
+        1u32 << (T::BITS - 1)
+    } else {
+        u32::MAX >> (32 - T::BITS)
+    };
+
+    let mut digits = 0;
+    while magnitude > 0 {
+        digits += 1;
+        magnitude /= radix as u32;
+    }
+
+    digits + negative as usize + 1
+}
+
+/// Gets the buffer size needed by [`format_f32`](fn.format_f32.html), including the terminator.
+pub const fn f32_len(width: i8, precision: u8) -> usize {
+    // A sign, and 39 digits for the largest float.
+    let mut len = 1 + 39;
+    if precision > 0 {
+        len += 1 + precision as usize;
+    }
+
+    let width = width.unsigned_abs() as usize;
+    if width > len { width + 1 } else { len + 1 }
+}
+
+/// Gets the buffer size needed by [`format_f32_exp`](fn.format_f32_exp.html), including the terminator.
+pub const fn f32_exp_len(precision: u8) -> usize {
+    // `dtostre` does not go beyond 7 digits after the decimal point.
+    let precision = if precision > 7 { 7 } else { precision as usize };
+
+    // A sign, a digit, the fraction and an exponent like `e+38`.
+    let mut len = 1 + 1 + 4;
+    if precision > 0 {
+        len += 1 + precision;
+    }
+    len + 1
+}
+
+struct IntCheck<T, const RADIX: u8, const N: usize>(PhantomData<T>);
+
+impl<T: Integer, const RADIX: u8, const N: usize> IntCheck<T, RADIX, N> {
+    const OK: () = assert!(N >= int_len::<T>(RADIX), "buffer too small, see `int_len`");
+}
+
+struct FloatCheck<const WIDTH: i8, const PRECISION: u8, const N: usize>;
+
+impl<const WIDTH: i8, const PRECISION: u8, const N: usize> FloatCheck<WIDTH, PRECISION, N> {
+    const OK: () = assert!(N >= f32_len(WIDTH, PRECISION), "buffer too small, see `f32_len`");
+}
+
+struct ExpCheck<const PRECISION: u8, const N: usize>;
+
+impl<const PRECISION: u8, const N: usize> ExpCheck<PRECISION, N> {
+    const OK: () = assert!(N >= f32_exp_len(PRECISION), "buffer too small, see `f32_exp_len`");
+}
+
+/// Converts an integer to decimal, using `itoa`, `utoa`, `ltoa` or `ultoa`.
+pub fn format_int<T: Integer, const N: usize>(value: T, buf: &mut [u8; N]) -> &str {
+    format_int_radix::<T, 10, N>(value, buf)
+}
+
+/// Converts an integer in base `RADIX`, from 2 to 36, using `itoa`, `utoa`, `ltoa` or `ultoa`.
+///
+/// Digits above 9 are written as lower case letters.
+pub fn format_int_radix<T: Integer, const RADIX: u8, const N: usize>(value: T, buf: &mut [u8; N]) -> &str {
+    let () = IntCheck::<T, RADIX, N>::OK;
+
+    unsafe {
+        value.write(buf.as_mut_ptr() as *mut c_char, RADIX);
+        written(buf)
+    }
+}
+
+/// Converts a float in the format `[-]d.ddd`, using `dtostrf`.
+///
+/// `PRECISION` is the number of digits after the decimal point. The result is
+/// padded with spaces to at least `WIDTH` characters, on the left, or on the
+/// right if `WIDTH` is negative. NaN and infinity are written as `NAN` and `INF`.
+pub fn format_f32<const WIDTH: i8, const PRECISION: u8, const N: usize>(value: f32, buf: &mut [u8; N]) -> &str {
+    let () = FloatCheck::<WIDTH, PRECISION, N>::OK;
+
+    unsafe {
+        dtostrf(value, WIDTH, PRECISION, buf.as_mut_ptr() as *mut c_char);
+        written(buf)
+    }
+}
+
+/// Converts a float in the format `[-]d.ddde±dd`, using `dtostre`.
+///
+/// `PRECISION` is the number of digits after the decimal point, up to 7. NaN
+/// and infinity are written as `nan` and `inf`, or in upper case with
+/// [`ExpFlags::UPPERCASE`](struct.ExpFlags.html#associatedconstant.UPPERCASE).
+pub fn format_f32_exp<const PRECISION: u8, const N: usize>(value: f32, flags: ExpFlags, buf: &mut [u8; N]) -> &str {
+    let () = ExpCheck::<PRECISION, N>::OK;
+
+    unsafe {
+        dtostre(value, buf.as_mut_ptr() as *mut c_char, PRECISION, flags.0);
+        written(buf)
+    }
+}
+
+/// Gets the string written to a buffer by a conversion function.
+unsafe fn written(buf: &[u8]) -> &str {
+    let len = strlen(buf.as_ptr() as *const c_char) as usize;
+    str::from_utf8_unchecked(&buf[..len])
+}
+
+/// Options for [`format_f32_exp`](fn.format_f32_exp.html), which can be combined with `|`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExpFlags(u8);
+
+impl ExpFlags {
+    /// No options: only negative numbers get a sign, and the exponent starts with `e`.
+    pub const NONE: ExpFlags = ExpFlags(0);
+    /// Puts a space in front of positive numbers, so they line up with negative ones.
+    pub const ALWAYS_SIGN: ExpFlags = ExpFlags(DTOSTR_ALWAYS_SIGN as u8);
+    /// Puts a `+` in front of positive numbers.
+    pub const PLUS_SIGN: ExpFlags = ExpFlags(DTOSTR_PLUS_SIGN as u8);
+    /// Writes the exponent with `E` instead of `e`.
+    pub const UPPERCASE: ExpFlags = ExpFlags(DTOSTR_UPPERCASE as u8);
+
+    /// Gets the flags as passed to `dtostre`.
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Checks if all flags in `other` are set.
+    pub const fn contains(self, other: ExpFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for ExpFlags {
+    type Output = ExpFlags;
+
+    fn bitor(self, rhs: ExpFlags) -> ExpFlags {
+        ExpFlags(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for ExpFlags {
+    fn bitor_assign(&mut self, rhs: ExpFlags) {
+        self.0 |= rhs.0;
+    }
+}
+
+// The lengths of the longest outputs, checked at compile time.
+const _: () = assert!(int_len::<i16>(10) == 7); // -32768
+const _: () = assert!(int_len::<u16>(10) == 6); // 65535
+const _: () = assert!(int_len::<u16>(16) == 5); // ffff
+const _: () = assert!(int_len::<i16>(16) == 5); // 8000
+const _: () = assert!(int_len::<i16>(2) == 17);
+const _: () = assert!(int_len::<i32>(10) == 12); // -2147483648
+const _: () = assert!(int_len::<u32>(10) == 11); // 4294967295
+const _: () = assert!(int_len::<u32>(36) == 8); // 1z141z3
+
+const _: () = assert!(f32_len(0, 0) == 41);
+const _: () = assert!(f32_len(0, 2) == 44);
+const _: () = assert!(f32_len(-8, 2) == 44);
+const _: () = assert!(f32_len(60, 2) == 61);
+const _: () = assert!(f32_len(-60, 2) == 61);
+
+const _: () = assert!(f32_exp_len(0) == 7); // -1e+38
+const _: () = assert!(f32_exp_len(3) == 11); // -1.000e+38
+const _: () = assert!(f32_exp_len(7) == 15);
+const _: () = assert!(f32_exp_len(20) == 15);
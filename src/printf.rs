@@ -0,0 +1,368 @@
+//! Type-checked wrappers around the printf family.
+//!
+//! Passing an argument of the wrong width to a variadic C function silently
+//! corrupts the stack, which is easy to do on AVR where `int` is only 16 bits.
+//! The [`avr_printf!`](../macro.avr_printf.html) and
+//! [`avr_snprintf!`](../macro.avr_snprintf.html) macros check every argument
+//! against its conversion when the program is compiled, place the format string
+//! in program memory and call the `*_P` variants of the functions.
+//!
+//! Arguments map to the following conversions:
+//!
+//! | Rust type                    | C type          | Conversions              |
+//! |------------------------------|-----------------|--------------------------|
+//! | `i8`, `u8`, `i16`, `u16`     | `int`           | `%c %d %i %u %o %x %X`   |
+//! | `i32`, `u32`                 | `long`          | `%ld %li %lu %lo %lx %lX`|
+//! | `f32`                        | `double`        | `%e %E %f %F %g %G`      |
+//! | `&CStr`                      | `char *`        | `%s`                     |
+//! | `PStr`                       | `PGM_P`         | `%S`                     |
+//! | `*const T`, `*mut T`         | `void *`        | `%p`                     |
+//!
+//! Floating point conversions need the `printf-float` feature, avr-libc's default
+//! `vfprintf` prints them as `?`.
+
+use bindings::{size_t, printf_P, snprintf_P};
+use progmem::PStr;
+use rust_ctypes::{c_char, c_int};
+use stdio;
+
+use core::ffi::CStr;
+use core::marker::PhantomData;
+
+/// Requests the `vfprintf` selected by the `printf-min` or `printf-float` feature.
+///
+/// Without a reference from outside of libc, the linker would only look for
+/// `vfprintf` when it reaches `printf_P` inside libc, and take libc's own version.
+#[cfg(any(feature = "printf-min", feature = "printf-float"))]
+#[used]
+static FORCE_VFPRINTF: unsafe extern "C" fn(*mut ::bindings::FILE, *const c_char, ::bindings::va_list) -> c_int =
+    ::bindings::vfprintf;
+
+/// The C type consumed by a conversion.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ArgKind {
+    /// `int` and `unsigned int`, which are 16 bits wide.
+    Int,
+    /// `long` and `unsigned long`, which are 32 bits wide.
+    Long,
+    /// `double`, which is 32 bits wide.
+    Double,
+    /// A string in RAM.
+    Str,
+    /// A string in program memory.
+    ProgStr,
+    /// A data pointer.
+    Pointer,
+}
+
+/// A `#[repr(transparent)]` wrapper for passing `int` through varargs.
+///
+/// rustc refuses to pass 16-bit integers to variadic functions because it
+/// assumes they are promoted to a wider `int`, which is not the case on AVR.
+#[doc(hidden)]
+#[repr(transparent)]
+pub struct VaInt(c_int);
+
+/// A `#[repr(transparent)]` wrapper for passing `double` through varargs.
+///
+/// `double` is 32 bits wide on AVR, but rustc refuses to pass `f32` to variadic functions.
+#[doc(hidden)]
+#[repr(transparent)]
+pub struct VaDouble(f32);
+
+/// A type which can be passed to the printf family.
+///
+/// # Safety
+///
+/// `Va` must be passed through varargs exactly like the C type described by `KIND`.
+pub unsafe trait PrintfArg {
+    /// The C type the argument is passed as.
+    const KIND: ArgKind;
+
+    #[doc(hidden)]
+    type Va;
+
+    #[doc(hidden)]
+    fn into_va(self) -> Self::Va;
+}
+
+macro_rules! impl_printf_int {
+    ($($ty:ty),*) => {
+        $(
+            unsafe impl PrintfArg for $ty {
+                const KIND: ArgKind = ArgKind::Int;
+                type Va = VaInt;
+
+                fn into_va(self) -> VaInt {
+                    VaInt(self as c_int)
+                }
+            }
+        )*
+    };
+}
+
+impl_printf_int!(i8, u8, i16, u16);
+
+unsafe impl PrintfArg for i32 {
+    const KIND: ArgKind = ArgKind::Long;
+    type Va = i32;
+
+    fn into_va(self) -> i32 {
+        self
+    }
+}
+
+unsafe impl PrintfArg for u32 {
+    const KIND: ArgKind = ArgKind::Long;
+    type Va = u32;
+
+    fn into_va(self) -> u32 {
+        self
+    }
+}
+
+unsafe impl PrintfArg for f32 {
+    const KIND: ArgKind = ArgKind::Double;
+    type Va = VaDouble;
+
+    fn into_va(self) -> VaDouble {
+        VaDouble(self)
+    }
+}
+
+unsafe impl PrintfArg for &CStr {
+    const KIND: ArgKind = ArgKind::Str;
+    type Va = *const c_char;
+
+    fn into_va(self) -> *const c_char {
+        self.as_ptr() as *const c_char
+    }
+}
+
+unsafe impl PrintfArg for PStr {
+    const KIND: ArgKind = ArgKind::ProgStr;
+    type Va = *const c_char;
+
+    fn into_va(self) -> *const c_char {
+        self.as_ptr()
+    }
+}
+
+unsafe impl<T> PrintfArg for *const T {
+    const KIND: ArgKind = ArgKind::Pointer;
+    type Va = *const T;
+
+    fn into_va(self) -> *const T {
+        self
+    }
+}
+
+unsafe impl<T> PrintfArg for *mut T {
+    const KIND: ArgKind = ArgKind::Pointer;
+    type Va = *mut T;
+
+    fn into_va(self) -> *mut T {
+        self
+    }
+}
+
+/// A tuple of printf arguments.
+#[doc(hidden)]
+pub trait PrintfArgs {
+    const KINDS: &'static [ArgKind];
+
+    unsafe fn printf(self, fmt: *const c_char) -> c_int;
+    unsafe fn snprintf(self, buf: *mut c_char, len: size_t, fmt: *const c_char) -> c_int;
+}
+
+macro_rules! impl_printf_args {
+    ($($name:ident . $idx:tt),*) => {
+        impl<$($name: PrintfArg),*> PrintfArgs for ($($name,)*) {
+            const KINDS: &'static [ArgKind] = &[$($name::KIND),*];
+
+            unsafe fn printf(self, fmt: *const c_char) -> c_int {
+                printf_P(fmt $(, self.$idx.into_va())*)
+            }
+
+            unsafe fn snprintf(self, buf: *mut c_char, len: size_t, fmt: *const c_char) -> c_int {
+                snprintf_P(buf, len, fmt $(, self.$idx.into_va())*)
+            }
+        }
+    };
+}
+
+impl_printf_args!();
+impl_printf_args!(A.0);
+impl_printf_args!(A.0, B.1);
+impl_printf_args!(A.0, B.1, C.2);
+impl_printf_args!(A.0, B.1, C.2, D.3);
+impl_printf_args!(A.0, B.1, C.2, D.3, E.4);
+impl_printf_args!(A.0, B.1, C.2, D.3, E.4, F.5);
+impl_printf_args!(A.0, B.1, C.2, D.3, E.4, F.5, G.6);
+impl_printf_args!(A.0, B.1, C.2, D.3, E.4, F.5, G.6, H.7);
+
+/// A format string, declared by the printf and scanf macros.
+#[doc(hidden)]
+pub trait Format {
+    const FMT: &'static str;
+
+    fn pstr() -> PStr;
+}
+
+/// Checks a format string against its arguments when the program is compiled.
+struct Check<F, A>(PhantomData<(F, A)>);
+
+impl<F: Format, A: PrintfArgs> Check<F, A> {
+    const OK: () = check(F::FMT, A::KINDS);
+}
+
+/// Checks that the conversions in a format string match the argument kinds.
+///
+/// Panics, which fails compilation when evaluated in a constant, on any mismatch.
+pub const fn check(fmt: &str, args: &[ArgKind]) {
+    let fmt = fmt.as_bytes();
+    let mut i = 0;
+    let mut arg = 0;
+
+    while i < fmt.len() {
+        if fmt[i] == 0 {
+            panic!("printf: the format string contains a NUL byte");
+        }
+        if fmt[i] != b'%' {
+            i += 1;
+            continue;
+        }
+
+        i += 1;
+        if i < fmt.len() && fmt[i] == b'%' {
+            i += 1;
+            continue;
+        }
+
+        // Flags, width, precision and length modifiers.
+        let mut long = false;
+        while i < fmt.len() {
+            match fmt[i] {
+                b'0'..=b'9' | b'-' | b'+' | b' ' | b'#' | b'.' | b'h' => i += 1,
+                b'l' if long => panic!("printf: `ll` is not supported by avr-libc"),
+                b'l' => {
+                    long = true;
+                    i += 1;
+                },
+                _ => break,
+            }
+        }
+
+        if i == fmt.len() {
+            panic!("printf: the format string ends inside a conversion");
+        }
+
+        let expected = match fmt[i] {
+            b'd' | b'i' | b'u' | b'o' | b'x' | b'X' if long => ArgKind::Long,
+            b'd' | b'i' | b'u' | b'o' | b'x' | b'X' | b'c' => ArgKind::Int,
+            b'e' | b'E' | b'f' | b'F' | b'g' | b'G' => ArgKind::Double,
+            b's' => ArgKind::Str,
+            b'S' => ArgKind::ProgStr,
+            b'p' => ArgKind::Pointer,
+            _ => panic!("printf: unsupported conversion in the format string"),
+        };
+
+        if arg == args.len() {
+            panic!("printf: too few arguments for the format string");
+        }
+        if args[arg] as u8 != expected as u8 {
+            panic!("printf: an argument does not match the type of its conversion");
+        }
+
+        arg += 1;
+        i += 1;
+    }
+
+    if arg != args.len() {
+        panic!("printf: too many arguments for the format string");
+    }
+}
+
+#[doc(hidden)]
+pub fn printf<F: Format, A: PrintfArgs>(args: A) -> c_int {
+    let () = Check::<F, A>::OK;
+
+    // vfprintf does not check for a missing stream.
+    if stdio::stdout().is_null() {
+        return -1;
+    }
+
+    unsafe { args.printf(F::pstr().as_ptr()) }
+}
+
+#[doc(hidden)]
+pub fn snprintf<F: Format, A: PrintfArgs>(buf: &mut [u8], args: A) -> c_int {
+    let () = Check::<F, A>::OK;
+
+    unsafe { args.snprintf(buf.as_mut_ptr() as *mut c_char, buf.len() as size_t, F::pstr().as_ptr()) }
+}
+
+/// Declares a format string for the printf and scanf macros.
+///
+/// A suffix is only appended to the string in program memory, and is not
+/// checked against the arguments.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __avr_format_string {
+    ($fmt:expr) => {
+        $crate::__avr_format_string!($fmt, "");
+    };
+    ($fmt:expr, $suffix:expr) => {
+        struct Fmt;
+
+        impl $crate::printf::Format for Fmt {
+            const FMT: &'static str = $fmt;
+
+            fn pstr() -> $crate::progmem::PStr {
+                $crate::progmem! {
+                    static FMT: PStr = concat!($fmt, $suffix);
+                }
+                FMT
+            }
+        }
+    };
+}
+
+/// Prints to `stdout` with a format string checked at compile time.
+///
+/// The format string must be a string literal, which is stored in program
+/// memory and passed to `printf_P`. Evaluates to the `c_int` returned by
+/// `printf_P`, or -1 if no `stdout` is installed.
+///
+/// See the [`printf`](printf/index.html) module for the supported arguments.
+///
+/// ```ignore
+/// avr_printf!("%s: %d of %ld\n", name, done as i16, total as i32);
+/// ```
+#[macro_export]
+macro_rules! avr_printf {
+    ($fmt:expr $(, $arg:expr)* $(,)?) => {{
+        $crate::__avr_format_string!($fmt);
+        $crate::printf::printf::<Fmt, _>(($($arg,)*))
+    }};
+}
+
+/// Formats into a byte buffer with a format string checked at compile time.
+///
+/// The output is always NUL-terminated and truncated to fit the buffer. Evaluates
+/// to the `c_int` returned by `snprintf_P`, which is the length the output would
+/// have had without truncation.
+///
+/// See the [`printf`](printf/index.html) module for the supported arguments.
+///
+/// ```ignore
+/// let mut buf = [0u8; 16];
+/// avr_snprintf!(&mut buf, "%u.%02u V", volts, centivolts);
+/// ```
+#[macro_export]
+macro_rules! avr_snprintf {
+    ($buf:expr, $fmt:expr $(, $arg:expr)* $(,)?) => {{
+        $crate::__avr_format_string!($fmt);
+        $crate::printf::snprintf::<Fmt, _>($buf, ($($arg,)*))
+    }};
+}
@@ -0,0 +1,372 @@
+//! Data stored in program memory (flash).
+//!
+//! On AVR, flash lives in a separate address space from RAM and can only be
+//! read with special instructions, so it is never accessed through ordinary
+//! references. Data is placed in flash with the [`progmem!`](../macro.progmem.html)
+//! macro and read back through [`ProgMem`](struct.ProgMem.html) and
+//! [`PStr`](struct.PStr.html), which use the `*_P` routines from `avr/pgmspace.h`.
+
+use bindings::{size_t, memcpy_P, memcmp_P, __strlen_P};
+use rust_ctypes::{c_char, c_int, c_void};
+
+use core::cmp::{self, Ordering};
+use core::{fmt, mem, str};
+
+/// A value stored in program memory.
+///
+/// Values of this type can only be created with the [`progmem!`](../macro.progmem.html)
+/// macro, which makes sure they are placed in flash.
+#[repr(transparent)]
+pub struct ProgMem<T> {
+    value: T,
+}
+
+// Flash is never written to at runtime.
+unsafe impl<T: Copy> Sync for ProgMem<T> { }
+
+impl<T: Copy> ProgMem<T> {
+    #[doc(hidden)]
+    pub const unsafe fn new(value: T) -> Self {
+        ProgMem { value }
+    }
+
+    /// Gets the address of the value in program memory.
+    pub fn as_ptr(&self) -> *const T {
+        &self.value
+    }
+
+    /// Copies the value into RAM.
+    pub fn read(&self) -> T {
+        unsafe { read_value(self.as_ptr()) }
+    }
+}
+
+impl<T: Copy, const N: usize> ProgMem<[T; N]> {
+    /// Gets the number of elements in the array.
+    pub fn len(&self) -> usize {
+        N
+    }
+
+    /// Checks if the array is empty.
+    pub fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// Copies a single element into RAM.
+    pub fn get(&self, index: usize) -> Option<T> {
+        if index < N {
+            Some(unsafe { read_value((self.as_ptr() as *const T).add(index)) })
+        } else {
+            None
+        }
+    }
+
+    /// Copies the elements starting at `start` into `buf`.
+    ///
+    /// Returns the number of elements copied, which is less than the length
+    /// of `buf` if the end of the array is reached.
+    pub fn copy_to(&self, start: usize, buf: &mut [T]) -> usize {
+        if start >= N {
+            return 0;
+        }
+        let count = cmp::min(buf.len(), N - start);
+
+        unsafe {
+            memcpy_P(buf.as_mut_ptr() as *mut c_void,
+                     (self.as_ptr() as *const T).add(start) as *const c_void,
+                     (count * mem::size_of::<T>()) as size_t);
+        }
+        count
+    }
+
+    /// Iterates over copies of the elements.
+    pub fn iter<'a>(&'a self) -> Iter<'a, T, N> {
+        Iter { array: self, index: 0 }
+    }
+}
+
+impl<'a, T: Copy, const N: usize> IntoIterator for &'a ProgMem<[T; N]> {
+    type Item = T;
+    type IntoIter = Iter<'a, T, N>;
+
+    fn into_iter(self) -> Iter<'a, T, N> {
+        self.iter()
+    }
+}
+
+/// An iterator over the elements of an array in program memory.
+pub struct Iter<'a, T: Copy + 'a, const N: usize> {
+    array: &'a ProgMem<[T; N]>,
+    index: usize,
+}
+
+impl<'a, T: Copy, const N: usize> Iterator for Iter<'a, T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let item = self.array.get(self.index)?;
+        self.index += 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = N - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, T: Copy, const N: usize> ExactSizeIterator for Iter<'a, T, N> { }
+
+/// A NUL-terminated string stored in program memory.
+///
+/// Strings are declared with the [`progmem!`](../macro.progmem.html) macro.
+#[derive(Copy, Clone)]
+pub struct PStr {
+    ptr: *const c_char,
+}
+
+// The string lives in flash, which is never written to at runtime.
+unsafe impl Sync for PStr { }
+unsafe impl Send for PStr { }
+
+impl PStr {
+    /// Creates a string from a pointer into program memory.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to a NUL-terminated, UTF-8 encoded string in program memory.
+    pub const unsafe fn from_ptr(ptr: *const u8) -> Self {
+        PStr { ptr: ptr as *const c_char }
+    }
+
+    /// Gets the address of the string in program memory.
+    pub fn as_ptr(&self) -> *const c_char {
+        self.ptr
+    }
+
+    /// Gets the length of the string in bytes, not including the terminator.
+    pub fn len(&self) -> usize {
+        unsafe { __strlen_P(self.ptr) as usize }
+    }
+
+    /// Checks if the string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.bytes().next().is_none()
+    }
+
+    /// Iterates over the bytes of the string.
+    pub fn bytes(&self) -> Bytes {
+        Bytes { ptr: self.ptr as *const u8 }
+    }
+
+    /// Copies the string into a RAM buffer.
+    ///
+    /// Returns `None` if the buffer is too small to hold the string.
+    pub fn copy_to<'a>(&self, buf: &'a mut [u8]) -> Option<&'a str> {
+        copy_str(*self, buf)
+    }
+
+    /// Compares the string with a string in RAM.
+    pub fn cmp_str(&self, other: &str) -> Ordering {
+        cmp_str(*self, self.len(), other)
+    }
+}
+
+impl FlashStr for PStr {
+    fn len(self) -> usize {
+        PStr::len(&self)
+    }
+
+    unsafe fn copy(self, offset: usize, buf: &mut [u8]) {
+        memcpy_P(buf.as_mut_ptr() as *mut c_void, self.ptr.add(offset) as *const c_void, buf.len() as size_t);
+    }
+
+    unsafe fn memcmp(self, other: &[u8]) -> c_int {
+        memcmp_P(other.as_ptr() as *const c_void, self.ptr as *const c_void, other.len() as size_t)
+    }
+}
+
+impl PartialEq<str> for PStr {
+    fn eq(&self, other: &str) -> bool {
+        eq_str(*self, other)
+    }
+}
+
+impl<'a> PartialEq<&'a str> for PStr {
+    fn eq(&self, other: &&'a str) -> bool {
+        *self == **other
+    }
+}
+
+impl PartialOrd<str> for PStr {
+    fn partial_cmp(&self, other: &str) -> Option<Ordering> {
+        Some(self.cmp_str(other))
+    }
+}
+
+impl fmt::Display for PStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_str(*self, f)
+    }
+}
+
+impl fmt::Debug for PStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\"{}\"", self)
+    }
+}
+
+/// An iterator over the bytes of a [`PStr`](struct.PStr.html).
+pub struct Bytes {
+    ptr: *const u8,
+}
+
+impl Iterator for Bytes {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = unsafe { read_value(self.ptr) };
+        if byte == 0 {
+            None
+        } else {
+            self.ptr = unsafe { self.ptr.add(1) };
+            Some(byte)
+        }
+    }
+}
+
+/// A NUL-terminated string in program memory, which is read through the
+/// near or far `*_P` routines.
+///
+/// Implemented by [`PStr`](struct.PStr.html) and `FarPStr`, which share the
+/// functions below.
+pub(crate) trait FlashStr: Copy {
+    /// Gets the length of the string in bytes, not including the terminator.
+    fn len(self) -> usize;
+
+    /// Copies the bytes starting at `offset` into `buf`, which must not reach
+    /// past the string.
+    unsafe fn copy(self, offset: usize, buf: &mut [u8]);
+
+    /// Compares `other` with the start of the string like `memcmp`, which must
+    /// not reach past the string.
+    unsafe fn memcmp(self, other: &[u8]) -> c_int;
+}
+
+/// Copies a string into a RAM buffer, or returns `None` if it does not fit.
+pub(crate) fn copy_str<S: FlashStr>(s: S, buf: &mut [u8]) -> Option<&str> {
+    let len = s.len();
+    if len > buf.len() {
+        return None;
+    }
+
+    unsafe {
+        s.copy(0, &mut buf[..len]);
+        Some(str::from_utf8_unchecked(&buf[..len]))
+    }
+}
+
+/// Compares a string of length `len` with a string in RAM.
+pub(crate) fn cmp_str<S: FlashStr>(s: S, len: usize, other: &str) -> Ordering {
+    let common = cmp::min(len, other.len());
+
+    // memcmp_P compares its RAM argument against the flash one.
+    let result = unsafe { s.memcmp(&other.as_bytes()[..common]) };
+
+    match result.cmp(&0).reverse() {
+        Ordering::Equal => len.cmp(&other.len()),
+        ordering => ordering,
+    }
+}
+
+/// Checks if a string is equal to a string in RAM, reading its length once.
+pub(crate) fn eq_str<S: FlashStr>(s: S, other: &str) -> bool {
+    let len = s.len();
+    len == other.len() && cmp_str(s, len, other) == Ordering::Equal
+}
+
+/// Writes a string, which has to be copied into RAM in chunks.
+pub(crate) fn fmt_str<S: FlashStr>(s: S, f: &mut fmt::Formatter) -> fmt::Result {
+    let len = s.len();
+    let mut buf = [0u8; 16];
+    let mut offset = 0;
+
+    while offset < len {
+        let count = cmp::min(buf.len(), len - offset);
+        unsafe { s.copy(offset, &mut buf[..count]) };
+
+        // Only write whole characters, the rest is picked up by the next chunk.
+        let chunk = match str::from_utf8(&buf[..count]) {
+            Ok(chunk) => chunk,
+            Err(e) => unsafe { str::from_utf8_unchecked(&buf[..e.valid_up_to()]) },
+        };
+        if chunk.is_empty() {
+            break;
+        }
+
+        f.write_str(chunk)?;
+        offset += chunk.len();
+    }
+    Ok(())
+}
+
+/// Copies a value out of program memory.
+unsafe fn read_value<T: Copy>(ptr: *const T) -> T {
+    let mut value = mem::MaybeUninit::<T>::uninit();
+    memcpy_P(value.as_mut_ptr() as *mut c_void, ptr as *const c_void, mem::size_of::<T>() as size_t);
+    value.assume_init()
+}
+
+#[doc(hidden)]
+pub const fn nul_terminated<const N: usize>(s: &str) -> [u8; N] {
+    let bytes = s.as_bytes();
+    let mut result = [0; N];
+    let mut i = 0;
+
+    while i < bytes.len() {
+        result[i] = bytes[i];
+        i += 1;
+    }
+    result
+}
+
+/// Places data in program memory, like `PROGMEM` does in C.
+///
+/// Statics of type `PStr` are initialized from a string literal and become
+/// NUL-terminated [`PStr`](progmem/struct.PStr.html)s. Every other static of
+/// type `T` becomes a [`ProgMem<T>`](progmem/struct.ProgMem.html).
+///
+/// ```ignore
+/// progmem! {
+///     static GREETING: PStr = "Hello, world!";
+///     /// Sine lookup table.
+///     pub static SINE: [u8; 4] = [0, 90, 127, 90];
+/// }
+///
+/// let third = SINE.get(2);
+/// if GREETING == "Hello, world!" { /* ... */ }
+/// ```
+#[macro_export]
+macro_rules! progmem {
+    () => {};
+    ($(#[$attr:meta])* $vis:vis static $name:ident : PStr = $s:expr; $($rest:tt)*) => {
+        $(#[$attr])*
+        $vis static $name: $crate::progmem::PStr = {
+            const STR: &'static str = $s;
+
+            #[link_section = ".progmem.data"]
+            static BYTES: [u8; STR.len() + 1] = $crate::progmem::nul_terminated(STR);
+
+            unsafe { $crate::progmem::PStr::from_ptr(&BYTES as *const [u8; STR.len() + 1] as *const u8) }
+        };
+
+        $crate::progmem! { $($rest)* }
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident : $ty:ty = $init:expr; $($rest:tt)*) => {
+        $(#[$attr])*
+        #[link_section = ".progmem.data"]
+        $vis static $name: $crate::progmem::ProgMem<$ty> = unsafe { $crate::progmem::ProgMem::new($init) };
+
+        $crate::progmem! { $($rest)* }
+    };
+}
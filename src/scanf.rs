@@ -0,0 +1,337 @@
+//! Type-checked parsing with `sscanf`.
+//!
+//! The [`avr_sscanf!`](../macro.avr_sscanf.html) macro takes the types to parse
+//! into instead of raw out-pointers, checks them against the conversions in the
+//! format string when the program is compiled and returns the parsed values.
+//!
+//! Outputs map to the following conversions:
+//!
+//! | Rust type      | C type          | Conversions                                  |
+//! |----------------|-----------------|----------------------------------------------|
+//! | `i8`, `u8`     | `char`          | `%c %hhd %hhi %hhu %hho %hhx %hhX`           |
+//! | `i16`, `u16`   | `int`           | `%d %i %u %o %x %X %p`                       |
+//! | `i32`, `u32`   | `long`          | `%ld %li %lu %lo %lx %lX`                    |
+//! | `f32`          | `float`         | `%e %E %f %F %g %G`                          |
+//! | `[u8; N]`      | `char[N]`       | `%Wc` with `W <= N`, `%Ws %W[...]` with `W < N` |
+//!
+//! Strings must be given a maximum width, which is checked against the size of
+//! the buffer, leaving room for the NUL terminator. Conversions with assignment
+//! suppression, like `%*d` or `%*s`, take no output and need no width. `%n` is
+//! not supported, as it is not counted as a converted field.
+//!
+//! Floating point conversions need the `scanf-float` feature, and `%[...]` is not
+//! available with `scanf-min`.
+
+use bindings::sscanf_P;
+use printf::Format;
+use rust_ctypes::{c_char, c_int};
+
+use core::ffi::CStr;
+use core::marker::PhantomData;
+use core::{fmt, mem};
+
+/// Requests the `vfscanf` selected by the `scanf-min` or `scanf-float` feature.
+///
+/// Without a reference from outside of libc, the linker would only look for
+/// `vfscanf` when it reaches `sscanf_P` inside libc, and take libc's own version.
+#[cfg(any(feature = "scanf-min", feature = "scanf-float"))]
+#[used]
+static FORCE_VFSCANF: unsafe extern "C" fn(*mut ::bindings::FILE, *const c_char, ::bindings::va_list) -> c_int =
+    ::bindings::vfscanf;
+
+/// An error returned when the input does not match the whole format string.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ScanError {
+    /// The input ended before the first field was converted.
+    Eof,
+    /// The input stopped matching the format string after the first `matched` fields.
+    Mismatch {
+        /// The number of fields which were converted.
+        matched: usize,
+    },
+}
+
+impl ScanError {
+    /// Gets the number of fields which were converted.
+    pub fn matched(&self) -> usize {
+        match *self {
+            ScanError::Eof => 0,
+            ScanError::Mismatch { matched } => matched,
+        }
+    }
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ScanError::Eof => f.write_str("input ended before the first field"),
+            ScanError::Mismatch { matched } => write!(f, "input stopped matching after {} fields", matched),
+        }
+    }
+}
+
+/// The C type written by a conversion.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ScanKind {
+    /// `char`, a single byte.
+    Char,
+    /// `int` and `unsigned int`, which are 16 bits wide.
+    Int,
+    /// `long` and `unsigned long`, which are 32 bits wide.
+    Long,
+    /// `float`, which is 32 bits wide.
+    Float,
+    /// A character buffer of the given size.
+    Buffer(usize),
+}
+
+/// A type which can be parsed into by the scanf family.
+///
+/// # Safety
+///
+/// The type must have the size and layout of the C type described by `KIND`,
+/// and the all-zero bit pattern must be a valid value.
+pub unsafe trait ScanArg {
+    /// The C type the output is written as.
+    const KIND: ScanKind;
+}
+
+macro_rules! impl_scan_arg {
+    ($kind:ident: $($ty:ty),*) => {
+        $(
+            unsafe impl ScanArg for $ty {
+                const KIND: ScanKind = ScanKind::$kind;
+            }
+        )*
+    };
+}
+
+impl_scan_arg!(Char: i8, u8);
+impl_scan_arg!(Int: i16, u16);
+impl_scan_arg!(Long: i32, u32);
+impl_scan_arg!(Float: f32);
+
+unsafe impl<const N: usize> ScanArg for [u8; N] {
+    const KIND: ScanKind = ScanKind::Buffer(N);
+}
+
+/// A tuple of scanf outputs.
+#[doc(hidden)]
+pub trait ScanArgs: Sized {
+    const KINDS: &'static [ScanKind];
+
+    /// Calls `sscanf_P`, with a pointer to an `int` for a trailing `%n` after the
+    /// outputs. Returns the number of converted fields, that `int`, which is left
+    /// at -1 if the `%n` was not reached, and the outputs.
+    unsafe fn sscanf(input: *const c_char, fmt: *const c_char) -> (c_int, c_int, Self);
+}
+
+macro_rules! impl_scan_args {
+    ($($name:ident . $idx:tt),*) => {
+        impl<$($name: ScanArg),*> ScanArgs for ($($name,)*) {
+            const KINDS: &'static [ScanKind] = &[$($name::KIND),*];
+
+            #[allow(unused_mut)]
+            unsafe fn sscanf(input: *const c_char, fmt: *const c_char) -> (c_int, c_int, Self) {
+                let mut outputs: Self = mem::zeroed();
+                let mut consumed: c_int = -1;
+                let matched = sscanf_P(input, fmt $(, &mut outputs.$idx as *mut $name)*, &mut consumed as *mut c_int);
+
+                (matched, consumed, outputs)
+            }
+        }
+    };
+}
+
+impl_scan_args!();
+impl_scan_args!(A.0);
+impl_scan_args!(A.0, B.1);
+impl_scan_args!(A.0, B.1, C.2);
+impl_scan_args!(A.0, B.1, C.2, D.3);
+impl_scan_args!(A.0, B.1, C.2, D.3, E.4);
+impl_scan_args!(A.0, B.1, C.2, D.3, E.4, F.5);
+impl_scan_args!(A.0, B.1, C.2, D.3, E.4, F.5, G.6);
+impl_scan_args!(A.0, B.1, C.2, D.3, E.4, F.5, G.6, H.7);
+
+/// Checks a format string against its outputs when the program is compiled.
+struct Check<F, A>(PhantomData<(F, A)>);
+
+impl<F: Format, A: ScanArgs> Check<F, A> {
+    const OK: () = check(F::FMT, A::KINDS);
+}
+
+/// What a conversion writes to its output.
+#[derive(Copy, Clone)]
+enum Conversion {
+    Char,
+    Int,
+    Long,
+    Float,
+    /// Exactly the given number of characters, without a terminator.
+    Chars(usize),
+    /// At most the given number of characters, followed by a terminator.
+    Str(usize),
+}
+
+impl Conversion {
+    const fn accepts(self, kind: ScanKind) -> bool {
+        match (self, kind) {
+            (Conversion::Char, ScanKind::Char) => true,
+            (Conversion::Int, ScanKind::Int) => true,
+            (Conversion::Long, ScanKind::Long) => true,
+            (Conversion::Float, ScanKind::Float) => true,
+            (Conversion::Chars(1), ScanKind::Char) => true,
+            (Conversion::Chars(width), ScanKind::Buffer(len)) => width <= len,
+            (Conversion::Str(width), ScanKind::Buffer(len)) => width < len,
+            _ => false,
+        }
+    }
+}
+
+/// Checks that the conversions in a format string match the output kinds.
+///
+/// Panics, which fails compilation when evaluated in a constant, on any mismatch.
+pub const fn check(fmt: &str, args: &[ScanKind]) {
+    let fmt = fmt.as_bytes();
+    let mut i = 0;
+    let mut arg = 0;
+
+    while i < fmt.len() {
+        if fmt[i] == 0 {
+            panic!("scanf: the format string contains a NUL byte");
+        }
+        if fmt[i] != b'%' {
+            i += 1;
+            continue;
+        }
+
+        i += 1;
+        if i < fmt.len() && fmt[i] == b'%' {
+            i += 1;
+            continue;
+        }
+
+        let suppress = i < fmt.len() && fmt[i] == b'*';
+        if suppress {
+            i += 1;
+        }
+
+        let mut width = 0;
+        let mut has_width = false;
+        while i < fmt.len() && fmt[i] >= b'0' && fmt[i] <= b'9' {
+            width = width * 10 + (fmt[i] - b'0') as usize;
+            has_width = true;
+            i += 1;
+        }
+        if has_width && width == 0 {
+            panic!("scanf: a field width must be greater than zero");
+        }
+
+        // Size modifiers, a single `h` is the default size.
+        let mut size = Conversion::Int;
+        if i < fmt.len() && fmt[i] == b'h' {
+            i += 1;
+            if i < fmt.len() && fmt[i] == b'h' {
+                size = Conversion::Char;
+                i += 1;
+            }
+        } else if i < fmt.len() && fmt[i] == b'l' {
+            size = Conversion::Long;
+            i += 1;
+        }
+
+        if i == fmt.len() {
+            panic!("scanf: the format string ends inside a conversion");
+        }
+
+        let expected = match fmt[i] {
+            b'd' | b'i' | b'u' | b'o' | b'x' | b'X' | b'p' => size,
+            b'e' | b'E' | b'f' | b'F' | b'g' | b'G' => Conversion::Float,
+            b'c' if has_width => Conversion::Chars(width),
+            b'c' => Conversion::Chars(1),
+            // Suppressed conversions store nothing, so they need no width.
+            b's' | b'[' if !has_width && suppress => Conversion::Str(0),
+            b's' | b'[' if !has_width => panic!("scanf: string conversions need a maximum field width"),
+            b's' | b'[' => Conversion::Str(width),
+            _ => panic!("scanf: unsupported conversion in the format string"),
+        };
+
+        if fmt[i] == b'[' {
+            i = skip_scanset(fmt, i + 1);
+        }
+        i += 1;
+
+        if suppress {
+            continue;
+        }
+
+        if arg == args.len() {
+            panic!("scanf: too few outputs for the format string");
+        }
+        if !expected.accepts(args[arg]) {
+            panic!("scanf: an output does not match the type or width of its conversion");
+        }
+        arg += 1;
+    }
+
+    if arg != args.len() {
+        panic!("scanf: too many outputs for the format string");
+    }
+}
+
+/// Finds the `]` closing a scanset which starts at `i`.
+const fn skip_scanset(fmt: &[u8], mut i: usize) -> usize {
+    if i < fmt.len() && fmt[i] == b'^' {
+        i += 1;
+    }
+    // A leading `]` is part of the set.
+    if i < fmt.len() && fmt[i] == b']' {
+        i += 1;
+    }
+    while i < fmt.len() && fmt[i] != b']' {
+        i += 1;
+    }
+
+    if i == fmt.len() {
+        panic!("scanf: unterminated `[` conversion");
+    }
+    i
+}
+
+#[doc(hidden)]
+pub fn sscanf<F: Format, A: ScanArgs>(input: &CStr) -> Result<A, ScanError> {
+    let () = Check::<F, A>::OK;
+
+    let (matched, consumed, outputs) = unsafe { A::sscanf(input.as_ptr() as *const c_char, F::pstr().as_ptr()) };
+
+    if matched < 0 {
+        Err(ScanError::Eof)
+    } else if matched as usize == A::KINDS.len() && consumed >= 0 {
+        Ok(outputs)
+    } else {
+        Err(ScanError::Mismatch { matched: matched as usize })
+    }
+}
+
+/// Parses a string with a format string checked at compile time.
+///
+/// Takes the input as a `&CStr`, a string literal used as the format string, which
+/// is stored in program memory and passed to `sscanf_P`, and a tuple of the types
+/// to parse into. Evaluates to a `Result` with a tuple of the parsed values, which
+/// is only `Ok` if every field was converted and the text after the last one
+/// matched as well.
+///
+/// See the [`scanf`](scanf/index.html) module for the supported outputs.
+///
+/// ```ignore
+/// let (channel, duty) = avr_sscanf!(line, "pwm %hhu %u", (u8, u16))?;
+/// let (name,) = avr_sscanf!(line, "name %15s", ([u8; 16],))?;
+/// ```
+#[macro_export]
+macro_rules! avr_sscanf {
+    ($input:expr, $fmt:expr, ($($ty:ty),* $(,)?)) => {{
+        // The `%n` is only reached if the whole format string matched.
+        $crate::__avr_format_string!($fmt, "%n");
+        $crate::scanf::sscanf::<Fmt, ($($ty,)*)>($input)
+    }};
+}
@@ -0,0 +1,289 @@
+//! Standard IO streams backed by Rust devices.
+//!
+//! avr-libc's stdio functions read and write characters through the `put` and
+//! `get` callbacks of a `FILE`. A [`Stream`](struct.Stream.html) connects those
+//! callbacks to a Rust [`PutChar`](trait.PutChar.html) or
+//! [`GetChar`](trait.GetChar.html) implementation, so that functions like
+//! `printf` and `puts` end up in, for example, a UART driver.
+//!
+//! ```ignore
+//! fn uart_put(byte: u8) -> Result<(), DeviceError> { /* ... */ }
+//!
+//! static mut UART: Stream<fn(u8) -> Result<(), DeviceError>> = Stream::writer(uart_put);
+//!
+//! unsafe { (*ptr::addr_of_mut!(UART)).set_stdout() };
+//! ```
+//!
+//! Rust formatting can be sent to any stream with a [`LibcWriter`](struct.LibcWriter.html).
+
+use bindings::{FILE, __iob, _FDEV_EOF, _FDEV_ERR, _FDEV_SETUP_READ, _FDEV_SETUP_WRITE};
+use bindings::{size_t, fwrite};
+use rust_ctypes::{c_char, c_int, c_void};
+
+use core::{fmt, ptr};
+
+/// An error reported by a stream device.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DeviceError {
+    /// There is no more data to read.
+    Eof,
+    /// The device failed.
+    Failed,
+}
+
+/// A device characters can be written to.
+pub trait PutChar {
+    /// Writes a single character.
+    fn put_char(&mut self, c: u8) -> Result<(), DeviceError>;
+}
+
+/// A device characters can be read from.
+pub trait GetChar {
+    /// Reads a single character.
+    fn get_char(&mut self) -> Result<u8, DeviceError>;
+}
+
+impl<F: FnMut(u8) -> Result<(), DeviceError>> PutChar for F {
+    fn put_char(&mut self, c: u8) -> Result<(), DeviceError> {
+        self(c)
+    }
+}
+
+impl<F: FnMut() -> Result<u8, DeviceError>> GetChar for F {
+    fn get_char(&mut self) -> Result<u8, DeviceError> {
+        self()
+    }
+}
+
+/// A stdio `FILE` backed by a Rust device.
+///
+/// The stream is usually kept in a static, so that it can be installed as one
+/// of the standard streams.
+#[repr(C)]
+pub struct Stream<D> {
+    // Must stay the first field, the callbacks cast the `FILE` pointer back to the stream.
+    file: FILE,
+    device: D,
+}
+
+impl<D: PutChar> Stream<D> {
+    /// Creates a write-only stream.
+    pub const fn writer(device: D) -> Self {
+        Stream::with_callbacks(device, Some(put::<D>), None, _FDEV_SETUP_WRITE as u8)
+    }
+}
+
+impl<D: GetChar> Stream<D> {
+    /// Creates a read-only stream.
+    pub const fn reader(device: D) -> Self {
+        Stream::with_callbacks(device, None, Some(get::<D>), _FDEV_SETUP_READ as u8)
+    }
+}
+
+impl<D: PutChar + GetChar> Stream<D> {
+    /// Creates a stream which can be both read and written.
+    pub const fn read_write(device: D) -> Self {
+        Stream::with_callbacks(device, Some(put::<D>), Some(get::<D>),
+                               (_FDEV_SETUP_READ | _FDEV_SETUP_WRITE) as u8)
+    }
+}
+
+impl<D> Stream<D> {
+    /// The equivalent of `FDEV_SETUP_STREAM`.
+    const fn with_callbacks(device: D,
+                            put: Option<unsafe extern "C" fn(c_char, *mut FILE) -> c_int>,
+                            get: Option<unsafe extern "C" fn(*mut FILE) -> c_int>,
+                            flags: u8) -> Self {
+        Stream {
+            file: FILE {
+                buf: ptr::null_mut(),
+                unget: 0,
+                flags,
+                size: 0,
+                len: 0,
+                put,
+                get,
+                udata: ptr::null_mut(),
+            },
+            device,
+        }
+    }
+
+    /// Gets the underlying `FILE`, for passing to the stdio functions.
+    pub fn as_file(&mut self) -> *mut FILE {
+        &mut self.file
+    }
+
+    /// Gets the device backing the stream.
+    pub fn device(&mut self) -> &mut D {
+        &mut self.device
+    }
+
+    /// Installs the stream as `stdin`.
+    pub fn set_stdin(&'static mut self) {
+        unsafe { set_standard_stream(0, self.as_file()) }
+    }
+
+    /// Installs the stream as `stdout`.
+    pub fn set_stdout(&'static mut self) {
+        unsafe { set_standard_stream(1, self.as_file()) }
+    }
+
+    /// Installs the stream as `stderr`.
+    pub fn set_stderr(&'static mut self) {
+        unsafe { set_standard_stream(2, self.as_file()) }
+    }
+}
+
+/// Gets the current `stdin`, which is null if none is installed.
+pub fn stdin() -> *mut FILE {
+    unsafe { standard_stream(0) }
+}
+
+/// Gets the current `stdout`, which is null if none is installed.
+pub fn stdout() -> *mut FILE {
+    unsafe { standard_stream(1) }
+}
+
+/// Gets the current `stderr`, which is null if none is installed.
+pub fn stderr() -> *mut FILE {
+    unsafe { standard_stream(2) }
+}
+
+/// Gets an entry of `__iob`, the array behind `stdin`, `stdout` and `stderr`.
+unsafe fn standard_stream(index: usize) -> *mut FILE {
+    let iob = ptr::addr_of_mut!(__iob) as *mut *mut FILE;
+    *iob.add(index)
+}
+
+unsafe fn set_standard_stream(index: usize, file: *mut FILE) {
+    let iob = ptr::addr_of_mut!(__iob) as *mut *mut FILE;
+    *iob.add(index) = file;
+}
+
+unsafe extern "C" fn put<D: PutChar>(c: c_char, file: *mut FILE) -> c_int {
+    let stream = file as *mut Stream<D>;
+
+    match (*stream).device.put_char(c as u8) {
+        Ok(()) => 0,
+        Err(_) => _FDEV_ERR as c_int,
+    }
+}
+
+unsafe extern "C" fn get<D: GetChar>(file: *mut FILE) -> c_int {
+    let stream = file as *mut Stream<D>;
+
+    match (*stream).device.get_char() {
+        Ok(c) => c as c_int,
+        Err(DeviceError::Eof) => _FDEV_EOF as c_int,
+        Err(DeviceError::Failed) => _FDEV_ERR as c_int,
+    }
+}
+
+/// A [`core::fmt::Write`](https://doc.rust-lang.org/core/fmt/trait.Write.html)
+/// implementation writing to a stdio `FILE`.
+///
+/// Output is collected in an `N` byte buffer and handed to `fwrite` whenever the
+/// buffer fills up, when [`flush`](#method.flush) is called and when the writer
+/// is dropped. The default of 16 bytes keeps the writer cheap to put on the stack;
+/// with `N = 0`, every string is passed straight to `fwrite`.
+///
+/// ```ignore
+/// let mut out = LibcWriter::<16>::stdout().unwrap();
+/// write!(out, "temperature: {}C", 21).unwrap();
+/// ```
+pub struct LibcWriter<const N: usize = 16> {
+    file: *mut FILE,
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> LibcWriter<N> {
+    /// Creates a writer for a stream.
+    ///
+    /// # Safety
+    ///
+    /// `file` must point to a writable `FILE` which outlives the writer.
+    pub unsafe fn new(file: *mut FILE) -> Self {
+        LibcWriter { file, buf: [0; N], len: 0 }
+    }
+
+    /// Creates a writer for `stdout`, if one is installed.
+    pub fn stdout() -> Option<Self> {
+        Self::standard(stdout())
+    }
+
+    /// Creates a writer for `stderr`, if one is installed.
+    pub fn stderr() -> Option<Self> {
+        Self::standard(stderr())
+    }
+
+    fn standard(file: *mut FILE) -> Option<Self> {
+        if file.is_null() {
+            None
+        } else {
+            Some(unsafe { Self::new(file) })
+        }
+    }
+
+    /// Gets the stream written to.
+    pub fn as_file(&self) -> *mut FILE {
+        self.file
+    }
+
+    /// Writes out any buffered bytes.
+    pub fn flush(&mut self) -> fmt::Result {
+        let len = self.len;
+        self.len = 0;
+
+        unsafe { write_all(self.file, &self.buf[..len]) }
+    }
+}
+
+impl<const N: usize> fmt::Write for LibcWriter<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let bytes = s.as_bytes();
+
+        if self.len + bytes.len() > N {
+            self.flush()?;
+        }
+
+        if bytes.len() > N {
+            unsafe { write_all(self.file, bytes) }
+        } else {
+            self.buf[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+            self.len += bytes.len();
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl<const N: usize> ufmt_write::uWrite for LibcWriter<N> {
+    type Error = fmt::Error;
+
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        fmt::Write::write_str(self, s)
+    }
+}
+
+impl<const N: usize> Drop for LibcWriter<N> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Writes bytes to a stream with `fwrite`.
+unsafe fn write_all(file: *mut FILE, bytes: &[u8]) -> fmt::Result {
+    if bytes.is_empty() {
+        return Ok(());
+    }
+
+    let written = fwrite(bytes.as_ptr() as *const c_void, 1, bytes.len() as size_t, file);
+
+    if written as usize == bytes.len() {
+        Ok(())
+    } else {
+        Err(fmt::Error)
+    }
+}
@@ -0,0 +1,351 @@
+//! The system clock and calendar time from `time.h`.
+//!
+//! avr-libc keeps the current time as a count of seconds since midnight,
+//! January 1st 2000 UTC (the Y2K epoch), rather than since 1970 like Unix.
+//! The clock is set once with [`set_system_time`](fn.set_system_time.html) and
+//! kept running by calling [`tick`](fn.tick.html) once per second, usually from
+//! a timer interrupt:
+//!
+//! ```ignore
+//! time::set_system_time(AvrInstant::from_unix(1_700_000_000).unwrap());
+//!
+//! #[avr_device::interrupt(atmega328p)]
+//! fn TIMER1_COMPA() {
+//!     time::tick();
+//! }
+//!
+//! let now = AvrInstant::now().to_utc();
+//! ```
+
+use bindings::{time_t, tm, UNIX_OFFSET};
+use bindings::{time, gmtime_r, localtime_r, mk_gmtime, mktime, month_length};
+use bindings::{set_system_time as raw_set_system_time, set_zone as raw_set_zone, system_tick};
+
+use core::ops::{Add, AddAssign, Sub, SubAssign};
+use core::time::Duration;
+use core::{fmt, ptr};
+
+/// The first year which can be represented by a timestamp.
+pub const MIN_YEAR: i16 = 2000;
+/// The last year which can be represented by a timestamp.
+///
+/// Timestamps only reach into this year until February 7th, 06:28:15 UTC.
+pub const MAX_YEAR: i16 = 2136;
+
+/// The month, day and time of the last timestamp, in `MAX_YEAR`.
+const LAST_TIME: (u8, u8, u8, u8, u8) = (2, 7, 6, 28, 15);
+
+/// A point in time, stored as seconds since the Y2K epoch.
+///
+/// Timestamps reach until February 7th 2136. Arithmetic with a `Duration`
+/// ignores fractions of a second.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AvrInstant(time_t);
+
+impl AvrInstant {
+    /// The Y2K epoch itself.
+    pub const EPOCH: AvrInstant = AvrInstant(0);
+
+    /// Gets the current system time.
+    pub fn now() -> Self {
+        AvrInstant(unsafe { time(ptr::null_mut()) })
+    }
+
+    /// Creates a timestamp from seconds since the Y2K epoch.
+    pub const fn from_y2k_secs(secs: u32) -> Self {
+        AvrInstant(secs)
+    }
+
+    /// Gets the seconds since the Y2K epoch.
+    pub const fn as_y2k_secs(&self) -> u32 {
+        self.0
+    }
+
+    /// Creates a timestamp from seconds since the Unix epoch.
+    ///
+    /// Returns `None` for times before 2000 or after the last representable timestamp.
+    pub fn from_unix(secs: u64) -> Option<Self> {
+        let secs = secs.checked_sub(UNIX_OFFSET as u64)?;
+
+        if secs <= time_t::MAX as u64 {
+            Some(AvrInstant(secs as time_t))
+        } else {
+            None
+        }
+    }
+
+    /// Gets the seconds since the Unix epoch.
+    pub fn to_unix(&self) -> u64 {
+        self.0 as u64 + UNIX_OFFSET as u64
+    }
+
+    /// Adds a duration, returning `None` on overflow.
+    pub fn checked_add(&self, duration: Duration) -> Option<Self> {
+        let secs = duration.as_secs();
+
+        if secs <= time_t::MAX as u64 {
+            self.0.checked_add(secs as time_t).map(AvrInstant)
+        } else {
+            None
+        }
+    }
+
+    /// Subtracts a duration, returning `None` when going before the Y2K epoch.
+    pub fn checked_sub(&self, duration: Duration) -> Option<Self> {
+        let secs = duration.as_secs();
+
+        if secs <= time_t::MAX as u64 {
+            self.0.checked_sub(secs as time_t).map(AvrInstant)
+        } else {
+            None
+        }
+    }
+
+    /// Gets the time elapsed since an earlier timestamp, or `None` if it is later than this one.
+    pub fn duration_since(&self, earlier: AvrInstant) -> Option<Duration> {
+        self.0.checked_sub(earlier.0).map(|secs| Duration::from_secs(secs as u64))
+    }
+
+    /// Gets the time elapsed since an earlier timestamp, or zero if it is later than this one.
+    pub fn saturating_duration_since(&self, earlier: AvrInstant) -> Duration {
+        self.duration_since(earlier).unwrap_or(Duration::from_secs(0))
+    }
+
+    /// Breaks the timestamp down into UTC calendar time.
+    pub fn to_utc(&self) -> Tm {
+        let mut raw = Tm::EPOCH.raw;
+        unsafe { gmtime_r(&self.0, &mut raw) };
+        Tm { raw }
+    }
+
+    /// Breaks the timestamp down into local calendar time, using the zone set
+    /// with [`set_zone`](fn.set_zone.html) and the daylight saving rule set with `set_dst`.
+    pub fn to_local(&self) -> Tm {
+        let mut raw = Tm::EPOCH.raw;
+        unsafe { localtime_r(&self.0, &mut raw) };
+        Tm { raw }
+    }
+}
+
+impl Add<Duration> for AvrInstant {
+    type Output = AvrInstant;
+
+    fn add(self, duration: Duration) -> AvrInstant {
+        self.checked_add(duration).expect("overflow when adding duration to timestamp")
+    }
+}
+
+impl AddAssign<Duration> for AvrInstant {
+    fn add_assign(&mut self, duration: Duration) {
+        *self = *self + duration;
+    }
+}
+
+impl Sub<Duration> for AvrInstant {
+    type Output = AvrInstant;
+
+    fn sub(self, duration: Duration) -> AvrInstant {
+        self.checked_sub(duration).expect("overflow when subtracting duration from timestamp")
+    }
+}
+
+impl SubAssign<Duration> for AvrInstant {
+    fn sub_assign(&mut self, duration: Duration) {
+        *self = *self - duration;
+    }
+}
+
+impl Sub<AvrInstant> for AvrInstant {
+    type Output = Duration;
+
+    /// Gets the time elapsed between two timestamps, saturating to zero.
+    fn sub(self, earlier: AvrInstant) -> Duration {
+        self.saturating_duration_since(earlier)
+    }
+}
+
+/// An error returned when creating a [`Tm`](struct.Tm.html) from out of range fields.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct InvalidTime;
+
+impl fmt::Display for InvalidTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("calendar time out of range")
+    }
+}
+
+/// Calendar time broken down into its components, wrapping `struct tm`.
+///
+/// The fields are always in range, and the year lies between
+/// [`MIN_YEAR`](constant.MIN_YEAR.html) and [`MAX_YEAR`](constant.MAX_YEAR.html).
+#[derive(Copy, Clone)]
+pub struct Tm {
+    raw: tm,
+}
+
+impl Tm {
+    /// Midnight, January 1st 2000.
+    pub const EPOCH: Tm = Tm {
+        raw: tm {
+            tm_sec: 0,
+            tm_min: 0,
+            tm_hour: 0,
+            tm_mday: 1,
+            tm_wday: 6,
+            tm_mon: 0,
+            tm_year: 100,
+            tm_yday: 0,
+            tm_isdst: 0,
+        },
+    };
+
+    /// Creates a calendar time from a date and a time of day.
+    ///
+    /// `month` ranges from 1 to 12 and `day` from 1 to the length of the month.
+    /// Times after the last timestamp are rejected. The day of the week and of
+    /// the year are filled in.
+    pub fn new(year: i16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Result<Self, InvalidTime> {
+        if !(MIN_YEAR..=MAX_YEAR).contains(&year) || !(1..=12).contains(&month) {
+            return Err(InvalidTime);
+        }
+        if day < 1 || day > days_in_month(year, month) || hour > 23 || minute > 59 || second > 59 {
+            return Err(InvalidTime);
+        }
+        if year == MAX_YEAR && (month, day, hour, minute, second) > LAST_TIME {
+            return Err(InvalidTime);
+        }
+
+        let mut raw = Tm::EPOCH.raw;
+        raw.tm_year = year - 1900;
+        raw.tm_mon = (month - 1) as i8;
+        raw.tm_mday = day as i8;
+        raw.tm_hour = hour as i8;
+        raw.tm_min = minute as i8;
+        raw.tm_sec = second as i8;
+
+        // Fills in the day of the week and of the year.
+        let time = unsafe { mk_gmtime(&raw) };
+        unsafe { gmtime_r(&time, &mut raw) };
+        Ok(Tm { raw })
+    }
+
+    /// Wraps a raw `struct tm`, checking that its fields are in range.
+    pub fn from_raw(raw: tm) -> Result<Self, InvalidTime> {
+        let year = raw.tm_year.checked_add(1900).ok_or(InvalidTime)?;
+        if raw.tm_mon < 0 || raw.tm_mday < 0 || raw.tm_hour < 0 || raw.tm_min < 0 || raw.tm_sec < 0 {
+            return Err(InvalidTime);
+        }
+        if raw.tm_wday < 0 || raw.tm_wday > 6 || raw.tm_yday < 0 || raw.tm_yday > 365 {
+            return Err(InvalidTime);
+        }
+
+        let checked = Tm::new(year, raw.tm_mon as u8 + 1, raw.tm_mday as u8,
+                              raw.tm_hour as u8, raw.tm_min as u8, raw.tm_sec as u8)?;
+        Ok(Tm { raw: tm { tm_isdst: raw.tm_isdst, ..checked.raw } })
+    }
+
+    /// Gets the underlying `struct tm`.
+    pub fn as_raw(&self) -> &tm {
+        &self.raw
+    }
+
+    /// Gets the year, for example 2024.
+    pub fn year(&self) -> i16 {
+        self.raw.tm_year + 1900
+    }
+
+    /// Gets the month, from 1 to 12.
+    pub fn month(&self) -> u8 {
+        self.raw.tm_mon as u8 + 1
+    }
+
+    /// Gets the day of the month, from 1 to 31.
+    pub fn day(&self) -> u8 {
+        self.raw.tm_mday as u8
+    }
+
+    /// Gets the hour, from 0 to 23.
+    pub fn hour(&self) -> u8 {
+        self.raw.tm_hour as u8
+    }
+
+    /// Gets the minute, from 0 to 59.
+    pub fn minute(&self) -> u8 {
+        self.raw.tm_min as u8
+    }
+
+    /// Gets the second, from 0 to 59.
+    pub fn second(&self) -> u8 {
+        self.raw.tm_sec as u8
+    }
+
+    /// Gets the day of the week, from 0 for Sunday to 6 for Saturday.
+    pub fn weekday(&self) -> u8 {
+        self.raw.tm_wday as u8
+    }
+
+    /// Gets the day of the year, from 0 to 365.
+    pub fn year_day(&self) -> u16 {
+        self.raw.tm_yday as u16
+    }
+
+    /// Gets how many seconds the clock is advanced for daylight saving time.
+    pub fn dst_offset(&self) -> i16 {
+        self.raw.tm_isdst
+    }
+
+    /// Converts the time to a timestamp, treating it as UTC.
+    pub fn to_instant_utc(&self) -> AvrInstant {
+        AvrInstant(unsafe { mk_gmtime(&self.raw) })
+    }
+
+    /// Converts the time to a timestamp, treating it as local time.
+    pub fn to_instant_local(&self) -> AvrInstant {
+        let mut raw = self.raw;
+        AvrInstant(unsafe { mktime(&mut raw) })
+    }
+}
+
+impl PartialEq for Tm {
+    fn eq(&self, other: &Tm) -> bool {
+        self.to_instant_utc() == other.to_instant_utc() && self.raw.tm_isdst == other.raw.tm_isdst
+    }
+}
+
+impl Eq for Tm { }
+
+impl fmt::Debug for Tm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Tm({})", self)
+    }
+}
+
+impl fmt::Display for Tm {
+    /// Formats the time like `isotime` does, for example `2013-03-23 01:03:52`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+               self.year(), self.month(), self.day(), self.hour(), self.minute(), self.second())
+    }
+}
+
+/// Gets the number of days in a month, where `month` ranges from 1 to 12.
+pub fn days_in_month(year: i16, month: u8) -> u8 {
+    unsafe { month_length(year, month) }
+}
+
+/// Sets the system time.
+pub fn set_system_time(now: AvrInstant) {
+    unsafe { raw_set_system_time(now.0) }
+}
+
+/// Sets the time zone used for local time, as an offset east of UTC.
+pub fn set_zone(offset_secs: i32) {
+    unsafe { raw_set_zone(offset_secs) }
+}
+
+/// Advances the system time by one second.
+///
+/// Call this from an interrupt handler that runs once per second.
+pub fn tick() {
+    unsafe { system_tick() }
+}